@@ -30,24 +30,34 @@ impl Executor {
     }
 
     pub fn enqueue_task(&self, task: Box<dyn Future<Output = ()> + Send + 'static>) {
-        let _ = async_runtime::spawn(task);
+        let handle = async_runtime::register_task(task);
+        async_runtime::wake_handle(handle);
         TASKS_REMAINING.fetch_add(1, Ordering::Relaxed);
     }
 
     pub fn start_workers(&self, num_workers: usize) -> ! {
         // Wrapper to match spawn_thread signature
         extern "C" fn worker_wrapper(arg: *mut u8) {
+            async_runtime::set_current_worker_id(arg as usize);
             worker_loop(arg)
         }
 
-        for _ in 0..num_workers {
+        // The main thread joins in as a worker too, so it gets a local run
+        // queue of its own - one more than `num_workers` spawned threads.
+        async_runtime::init_worker_queues(num_workers + 1);
+
+        for id in 0..num_workers {
             let _ = async_syscall::spawn_thread(
                 worker_wrapper,
-                core::ptr::null_mut(),
+                id as *mut u8,
                 async_runtime::WORKER_STACK_SIZE,
             );
         }
-        // Main thread becomes a worker too
+        // Main thread becomes a worker too. Unlike the cloned workers above,
+        // it never went through spawn_thread's CLONE_SETTLS, so it needs its
+        // own TLS block before any coop-budget access.
+        async_syscall::init_thread_tls();
+        async_runtime::set_current_worker_id(num_workers);
         worker_loop(core::ptr::null_mut())
     }
 
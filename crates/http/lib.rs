@@ -6,11 +6,34 @@ extern crate alloc;
 use alloc::vec::Vec;
 use async_net::RecvFuture;
 use async_net::SendFuture;
+use async_net::SendVectoredFuture;
 use async_syscall as sys;
+use async_utils::parsing;
 
 // Embed the HTML into the binary so HTTP handler can serve it directly.
 static INDEX_HTML: &[u8] = include_bytes!("../../html/index.html");
 
+/// Read and decode a chunked request body, recv'ing more data as needed.
+/// `buf` holds bytes already read from the connection starting at the first
+/// byte of the body; bytes belonging to the next request (if any) are left
+/// untouched. Returns `None` if the client exceeds `parsing::MAX_CHUNKED_BODY`,
+/// sends a malformed chunked body, or disconnects before the terminating
+/// chunk arrives.
+async fn recv_chunked_body(fd: i32, mut buf: Vec<u8>) -> Option<Vec<u8>> {
+    loop {
+        match parsing::parse_chunked(&buf, parsing::MAX_CHUNKED_BODY) {
+            Ok(Some((_consumed, decoded, true))) => return Some(decoded),
+            Ok(_) => {}
+            Err(_) => return None,
+        }
+        let more = RecvFuture::new(fd, 4096).await;
+        if more.is_empty() {
+            return None;
+        }
+        buf.extend_from_slice(&more);
+    }
+}
+
 pub fn http_response_headers(status: &str, content_type: &str, content_len: usize) -> Vec<u8> {
     let mut v = Vec::new();
     v.extend_from_slice(b"HTTP/1.1 ");
@@ -75,10 +98,12 @@ pub async fn handle_http_connection(fd: i32) {
         sys::write_usize(async_runtime::LOG_FD.load(core::sync::atomic::Ordering::Relaxed), fd as usize);
         async_runtime::log_write(b" route=/ (index)\n");
         
-        let mut resp =
+        // Send headers and the embedded page as two iovecs via writev
+        // instead of copying INDEX_HTML into a freshly allocated response
+        // buffer on every request.
+        let headers =
             http_response_headers("200 OK", "text/html; charset=utf-8", INDEX_HTML.len());
-        resp.extend_from_slice(INDEX_HTML);
-        let _ = SendFuture::new(fd, &resp).await;
+        let _ = SendVectoredFuture::new(fd, &[&headers, INDEX_HTML]).await;
     } else if buf.starts_with(b"GET /term ")
         || buf.starts_with(b"GET /term")
         || buf.starts_with(b"GET /ws ")
@@ -92,6 +117,31 @@ pub async fn handle_http_connection(fd: i32) {
         // NOTE: WebSocket handler manages fd lifetime, doesn't return until connection closes
         async_websocket::accept_and_run(fd, &buf).await;
         return;
+    } else if parsing::find_header_value(&buf, "Transfer-Encoding").is_some() {
+        async_runtime::log_write(b"[HTTP] fd=");
+        sys::write_usize(async_runtime::LOG_FD.load(core::sync::atomic::Ordering::Relaxed), fd as usize);
+        async_runtime::log_write(b" route=chunked body\n");
+
+        let body_start = buf
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .map(|p| p + 4)
+            .unwrap_or(buf.len());
+        let pending = buf[body_start..].to_vec();
+        match recv_chunked_body(fd, pending).await {
+            Some(decoded) => {
+                let mut resp = Vec::new();
+                resp.extend_from_slice(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\n\r\n");
+                resp.extend_from_slice(&parsing::build_chunked(&decoded));
+                let _ = SendFuture::new(fd, &resp).await;
+            }
+            None => {
+                let body = b"Bad Request\n";
+                let mut resp = http_response_headers("400 Bad Request", "text/plain", body.len());
+                resp.extend_from_slice(body);
+                let _ = SendFuture::new(fd, &resp).await;
+            }
+        }
     } else {
         async_runtime::log_write(b"[HTTP] fd=");
         sys::write_usize(async_runtime::LOG_FD.load(core::sync::atomic::Ordering::Relaxed), fd as usize);
@@ -10,6 +10,9 @@ use core::task::{Context, Poll};
 pub const AF_INET: i32 = 2;
 pub const SOCK_STREAM: i32 = 1;
 
+mod resolve;
+pub use resolve::{resolve_a_record, DEFAULT_RESOLVER_ADDR, SOCK_DGRAM};
+
 #[repr(C)]
 pub struct SockAddrIn {
     pub sin_family: u16,
@@ -33,18 +36,22 @@ impl AcceptFuture {
 impl core::future::Future for AcceptFuture {
     type Output = isize;
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if !async_runtime::coop::poll_proceed(cx) {
+            return Poll::Pending;
+        }
         let mut sa_buf = [0u8; 32];
         let mut salen: usize = sa_buf.len();
-        let r = async_syscall::accept4(self.fd, sa_buf.as_mut_ptr(), &mut salen as *mut usize, 0);
-        if r >= 0 { return Poll::Ready(r); }
-        if r == -11 {
-            if !self.registered {
-                async_runtime::register_fd_waker(self.fd, 0x0001, cx.waker().clone());
-                self.registered = true;
+        match async_syscall::accept4(self.fd, sa_buf.as_mut_ptr(), &mut salen as *mut usize, 0) {
+            Ok(cfd) => Poll::Ready(cfd as isize),
+            Err(async_syscall::Errno::EAGAIN) => {
+                if !self.registered {
+                    async_runtime::register_fd_waker(self.fd, 0x0001, cx.waker().clone());
+                    self.registered = true;
+                }
+                Poll::Pending
             }
-            return Poll::Pending;
+            Err(e) => Poll::Ready(-(e.0 as isize)),
         }
-        Poll::Ready(r)
     }
 }
 
@@ -69,16 +76,20 @@ impl ConnectFuture {
 impl core::future::Future for ConnectFuture {
     type Output = isize;
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let r = async_syscall::connect(self.fd, self.addr.as_ptr(), self.addrlen);
-        if r >= 0 { return Poll::Ready(r); }
-        if r == -115 || r == -11 {
-            if !self.registered {
-                async_runtime::register_fd_waker(self.fd, 0x0004, cx.waker().clone());
-                self.registered = true;
-            }
+        if !async_runtime::coop::poll_proceed(cx) {
             return Poll::Pending;
         }
-        Poll::Ready(r)
+        match async_syscall::connect(self.fd, self.addr.as_ptr(), self.addrlen) {
+            Ok(v) => Poll::Ready(v as isize),
+            Err(async_syscall::Errno::EINPROGRESS) | Err(async_syscall::Errno::EAGAIN) => {
+                if !self.registered {
+                    async_runtime::register_fd_waker(self.fd, 0x0004, cx.waker().clone());
+                    self.registered = true;
+                }
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(-(e.0 as isize)),
+        }
     }
 }
 
@@ -99,25 +110,27 @@ impl RecvFuture {
 impl core::future::Future for RecvFuture {
     type Output = Vec<u8>;
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let r = async_syscall::recvfrom(self.fd, self.buf.as_mut_ptr(), self.buf.len(), 0,
-                                        core::ptr::null_mut(), core::ptr::null_mut());
-        if r > 0 {
-            unsafe { self.buf.set_len(r as usize); }
-            return Poll::Ready(core::mem::take(&mut self.buf));
-        }
-        if r == 0 {
-            unsafe { self.buf.set_len(0); }
-            return Poll::Ready(core::mem::take(&mut self.buf));
-        }
-        if r == -11 {
-            if !self.registered {
-                async_runtime::register_fd_waker(self.fd, 0x0001, cx.waker().clone());
-                self.registered = true;
-            }
+        if !async_runtime::coop::poll_proceed(cx) {
             return Poll::Pending;
         }
-        unsafe { self.buf.set_len(0); }
-        Poll::Ready(core::mem::take(&mut self.buf))
+        match async_syscall::recvfrom(self.fd, self.buf.as_mut_ptr(), self.buf.len(), 0,
+                                      core::ptr::null_mut(), core::ptr::null_mut()) {
+            Ok(n) => {
+                unsafe { self.buf.set_len(n); }
+                Poll::Ready(core::mem::take(&mut self.buf))
+            }
+            Err(async_syscall::Errno::EAGAIN) => {
+                if !self.registered {
+                    async_runtime::register_fd_waker(self.fd, 0x0001, cx.waker().clone());
+                    self.registered = true;
+                }
+                Poll::Pending
+            }
+            Err(_) => {
+                unsafe { self.buf.set_len(0); }
+                Poll::Ready(core::mem::take(&mut self.buf))
+            }
+        }
     }
 }
 
@@ -138,16 +151,340 @@ impl SendFuture {
 impl core::future::Future for SendFuture {
     type Output = isize;
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        let r = async_syscall::sendto(self.fd, self.buf.as_ptr(), self.buf.len(), 0,
-                                      core::ptr::null(), 0);
-        if r >= 0 { return Poll::Ready(r); }
-        if r == -11 {
-            if !self.registered {
-                async_runtime::register_fd_waker(self.fd, 0x0004, cx.waker().clone());
-                self.registered = true;
+        if !async_runtime::coop::poll_proceed(cx) {
+            return Poll::Pending;
+        }
+        match async_syscall::sendto(self.fd, self.buf.as_ptr(), self.buf.len(), 0,
+                                    core::ptr::null(), 0) {
+            Ok(n) => Poll::Ready(n as isize),
+            Err(async_syscall::Errno::EAGAIN) => {
+                if !self.registered {
+                    async_runtime::register_fd_waker(self.fd, 0x0004, cx.waker().clone());
+                    self.registered = true;
+                }
+                Poll::Pending
             }
+            Err(e) => Poll::Ready(-(e.0 as isize)),
+        }
+    }
+}
+
+/// Send multiple slices in one `writev` without merging them into a single
+/// buffer first - e.g. response headers built on the stack/heap plus a
+/// `&'static` embedded body served directly by reference. Tracks partial
+/// writes across polls: `done` is the index of the first buffer not yet
+/// fully sent, `offset` how far into it the last `writev` got, and `sent` is
+/// the running total returned on completion - not just the last `writev`
+/// call's count, since a multi-poll send would otherwise under-report.
+pub struct SendVectoredFuture<'a> {
+    fd: i32,
+    bufs: &'a [&'a [u8]],
+    done: usize,
+    offset: usize,
+    sent: usize,
+}
+
+impl<'a> SendVectoredFuture<'a> {
+    pub fn new(fd: i32, bufs: &'a [&'a [u8]]) -> Self {
+        Self { fd, bufs, done: 0, offset: 0, sent: 0 }
+    }
+}
+
+impl<'a> core::future::Future for SendVectoredFuture<'a> {
+    type Output = isize;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.done >= self.bufs.len() {
+            return Poll::Ready(self.sent as isize);
+        }
+        if !async_runtime::coop::poll_proceed(cx) {
+            return Poll::Pending;
+        }
+
+        let iov: Vec<async_syscall::IoSlice> = self.bufs[self.done..]
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                if i == 0 {
+                    async_syscall::IoSlice::new(&b[self.offset..])
+                } else {
+                    async_syscall::IoSlice::new(b)
+                }
+            })
+            .collect();
+
+        let r = async_syscall::writev(self.fd, &iov);
+        drop(iov);
+
+        match r {
+            Ok(n) => {
+                self.sent += n;
+                let mut remaining = n;
+                while remaining > 0 && self.done < self.bufs.len() {
+                    let avail = self.bufs[self.done].len() - self.offset;
+                    if remaining < avail {
+                        self.offset += remaining;
+                        remaining = 0;
+                    } else {
+                        remaining -= avail;
+                        self.done += 1;
+                        self.offset = 0;
+                    }
+                }
+                if self.done >= self.bufs.len() {
+                    return Poll::Ready(self.sent as isize);
+                }
+                // Partial write - fall through and wait for the socket to drain.
+            }
+            Err(async_syscall::Errno::EAGAIN) => {}
+            Err(e) => return Poll::Ready(-(e.0 as isize)),
+        }
+
+        // Always re-register: the reactor consumes the waker once it fires,
+        // so a future still waiting after a partial write needs a fresh one.
+        async_runtime::register_fd_waker(self.fd, 0x0004, cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Scatter-read counterpart to `SendVectoredFuture`, backed by `readv`.
+/// Ready as soon as any bytes arrive (like `RecvFuture`), with earlier
+/// buffers filled before later ones per `readv` semantics.
+pub struct RecvVectoredFuture {
+    fd: i32,
+    bufs: Vec<Vec<u8>>,
+}
+
+impl RecvVectoredFuture {
+    pub fn new(fd: i32, caps: &[usize]) -> Self {
+        let bufs = caps
+            .iter()
+            .map(|&cap| {
+                let mut v = Vec::with_capacity(cap);
+                unsafe { v.set_len(cap) };
+                v
+            })
+            .collect();
+        Self { fd, bufs }
+    }
+}
+
+impl core::future::Future for RecvVectoredFuture {
+    type Output = Vec<Vec<u8>>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if !async_runtime::coop::poll_proceed(cx) {
             return Poll::Pending;
         }
-        Poll::Ready(r)
+        let mut iov: Vec<async_syscall::IoSliceMut> = self
+            .bufs
+            .iter_mut()
+            .map(|b| async_syscall::IoSliceMut::new(b))
+            .collect();
+        let r = async_syscall::readv(self.fd, &mut iov);
+        drop(iov);
+
+        match r {
+            Ok(n) => {
+                let mut remaining = n;
+                for b in self.bufs.iter_mut() {
+                    let take = remaining.min(b.len());
+                    unsafe { b.set_len(take) };
+                    remaining -= take;
+                }
+                Poll::Ready(core::mem::take(&mut self.bufs))
+            }
+            Err(async_syscall::Errno::EAGAIN) => {
+                async_runtime::register_fd_waker(self.fd, 0x0001, cx.waker().clone());
+                Poll::Pending
+            }
+            Err(_) => {
+                for b in self.bufs.iter_mut() {
+                    unsafe { b.set_len(0) };
+                }
+                Poll::Ready(core::mem::take(&mut self.bufs))
+            }
+        }
+    }
+}
+
+/// How much to `splice` per kernel call - generous enough to amortize the
+/// syscall, and equal to the pipe's default capacity on Linux (16 pages,
+/// 64 KiB with 4 KiB pages), so one call can fill the bounce pipe but never
+/// asks for more than it holds.
+const SPLICE_CHUNK: usize = 64 * 1024;
+
+/// The pipe a `splice`-based relay bounces bytes through - `splice` can only
+/// move data into or out of a pipe, never directly between two sockets.
+struct Pipe {
+    read_fd: i32,
+    write_fd: i32,
+}
+
+impl Pipe {
+    fn new() -> Result<Self, async_syscall::Errno> {
+        let (read_fd, write_fd) = async_syscall::pipe2(0)?;
+        Ok(Self { read_fd, write_fd })
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        let _ = async_syscall::close(self.read_fd);
+        let _ = async_syscall::close(self.write_fd);
+    }
+}
+
+/// One direction of a zero-copy relay: `splice`s `want` bytes total from a
+/// source fd into `pipe`, then out to a destination fd, never touching a
+/// userspace buffer. Shared by [`SpliceAll`] and [`CopyBidirectional`], both
+/// of which just supply the `(src, dst)` pair each poll.
+struct RelayState {
+    pipe: Pipe,
+    want: usize,
+    moved: usize,
+    buffered: usize,
+    src_eof: bool,
+    src_registered: bool,
+    dst_registered: bool,
+}
+
+impl RelayState {
+    fn new(want: usize) -> Result<Self, async_syscall::Errno> {
+        Ok(Self {
+            pipe: Pipe::new()?,
+            want,
+            moved: 0,
+            buffered: 0,
+            src_eof: false,
+            src_registered: false,
+            dst_registered: false,
+        })
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+        src: i32,
+        dst: i32,
+    ) -> Poll<Result<usize, async_syscall::Errno>> {
+        use async_syscall::SpliceFlags;
+        let flags = SpliceFlags::MOVE | SpliceFlags::NONBLOCK;
+        loop {
+            // A fully kernel-buffered relay (src always has data, dst always
+            // has room) would otherwise splice in a tight loop forever
+            // within this single poll() call, starving every other task on
+            // this worker - check the cooperative budget each iteration,
+            // not just once at entry.
+            if !async_runtime::coop::poll_proceed(cx) {
+                return Poll::Pending;
+            }
+            if self.buffered > 0 {
+                match async_syscall::splice(self.pipe.read_fd, dst, self.buffered, flags) {
+                    Ok(n) => {
+                        self.buffered -= n;
+                        self.moved += n;
+                        continue;
+                    }
+                    Err(async_syscall::Errno::EAGAIN) => {
+                        if !self.dst_registered {
+                            async_runtime::register_fd_waker(dst, 0x0004, cx.waker().clone());
+                            self.dst_registered = true;
+                        }
+                        return Poll::Pending;
+                    }
+                    Err(e) => return Poll::Ready(Err(e)),
+                }
+            }
+
+            if self.moved >= self.want || self.src_eof {
+                return Poll::Ready(Ok(self.moved));
+            }
+
+            let chunk = (self.want - self.moved).min(SPLICE_CHUNK);
+            match async_syscall::splice(src, self.pipe.write_fd, chunk, flags) {
+                Ok(0) => {
+                    self.src_eof = true;
+                }
+                Ok(n) => {
+                    self.buffered += n;
+                }
+                Err(async_syscall::Errno::EAGAIN) => {
+                    if !self.src_registered {
+                        async_runtime::register_fd_waker(src, 0x0001, cx.waker().clone());
+                        self.src_registered = true;
+                    }
+                    return Poll::Pending;
+                }
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+    }
+}
+
+/// Relay up to `len` bytes from `src` to `dst` without copying through a
+/// userspace buffer. Resolves to the number of bytes actually moved, which
+/// is less than `len` if `src` hit EOF first.
+pub struct SpliceAll {
+    src: i32,
+    dst: i32,
+    state: RelayState,
+}
+
+impl SpliceAll {
+    pub fn new(src: i32, dst: i32, len: usize) -> Result<Self, async_syscall::Errno> {
+        Ok(Self { src, dst, state: RelayState::new(len)? })
+    }
+}
+
+impl core::future::Future for SpliceAll {
+    type Output = Result<usize, async_syscall::Errno>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.state.poll(cx, this.src, this.dst)
+    }
+}
+
+/// Relay bytes in both directions between `a` and `b` at once (e.g. proxying
+/// a client socket to an upstream one) until each side has moved `len` bytes
+/// or hit EOF. Resolves to the final result of each direction.
+pub struct CopyBidirectional {
+    a: i32,
+    b: i32,
+    a_to_b: RelayState,
+    b_to_a: RelayState,
+    a_to_b_done: Option<Result<usize, async_syscall::Errno>>,
+    b_to_a_done: Option<Result<usize, async_syscall::Errno>>,
+}
+
+impl CopyBidirectional {
+    pub fn new(a: i32, b: i32, len: usize) -> Result<Self, async_syscall::Errno> {
+        Ok(Self {
+            a,
+            b,
+            a_to_b: RelayState::new(len)?,
+            b_to_a: RelayState::new(len)?,
+            a_to_b_done: None,
+            b_to_a_done: None,
+        })
+    }
+}
+
+impl core::future::Future for CopyBidirectional {
+    type Output = (Result<usize, async_syscall::Errno>, Result<usize, async_syscall::Errno>);
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if this.a_to_b_done.is_none() {
+            if let Poll::Ready(r) = this.a_to_b.poll(cx, this.a, this.b) {
+                this.a_to_b_done = Some(r);
+            }
+        }
+        if this.b_to_a_done.is_none() {
+            if let Poll::Ready(r) = this.b_to_a.poll(cx, this.b, this.a) {
+                this.b_to_a_done = Some(r);
+            }
+        }
+        match (this.a_to_b_done, this.b_to_a_done) {
+            (Some(x), Some(y)) => Poll::Ready((x, y)),
+            _ => Poll::Pending,
+        }
     }
 }
@@ -0,0 +1,186 @@
+//! Minimal stub DNS resolver.
+//!
+//! Resolves a hostname to an IPv4 address so `ConnectFuture` callers aren't
+//! stuck hardcoding addresses. Built as a plain `async fn` composed from the
+//! socket futures in the parent module, the same way higher-level protocol
+//! handlers elsewhere in this codebase layer on top of the raw socket
+//! futures.
+
+use crate::{htons, AF_INET, ConnectFuture, RecvFuture, SendFuture, SockAddrIn};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU16, Ordering};
+
+pub const SOCK_DGRAM: i32 = 2;
+
+/// 8.8.8.8 in network byte order, used when the caller doesn't configure one.
+pub const DEFAULT_RESOLVER_ADDR: u32 = u32::from_be_bytes([8, 8, 8, 8]);
+
+static NEXT_TXN_ID: AtomicU16 = AtomicU16::new(0x1234);
+
+fn next_txn_id() -> u16 {
+    NEXT_TXN_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Construct an IPv4 `SockAddrIn` with the given host-order port and IPv4 address.
+fn inet4_sockaddr(port: u16, addr: u32) -> SockAddrIn {
+    SockAddrIn { sin_family: AF_INET as u16, sin_port: htons(port), sin_addr: addr, sin_zero: [0u8; 8] }
+}
+
+fn sockaddr_bytes(addr: &SockAddrIn) -> &[u8] {
+    unsafe {
+        core::slice::from_raw_parts(
+            addr as *const SockAddrIn as *const u8,
+            core::mem::size_of::<SockAddrIn>(),
+        )
+    }
+}
+
+/// Build a standard-query A-record DNS request: 16-bit id, flags 0x0100
+/// (recursion desired), one question, QNAME as length-prefixed labels,
+/// QTYPE=A(1), QCLASS=IN(1).
+fn build_query(txn_id: u16, hostname: &str) -> Vec<u8> {
+    let mut pkt = Vec::new();
+    pkt.extend_from_slice(&txn_id.to_be_bytes());
+    pkt.extend_from_slice(&0x0100u16.to_be_bytes());
+    pkt.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    pkt.extend_from_slice(&0u16.to_be_bytes()); // ANCOUNT
+    pkt.extend_from_slice(&0u16.to_be_bytes()); // NSCOUNT
+    pkt.extend_from_slice(&0u16.to_be_bytes()); // ARCOUNT
+    for label in hostname.split('.') {
+        pkt.push(label.len() as u8);
+        pkt.extend_from_slice(label.as_bytes());
+    }
+    pkt.push(0); // root label
+    pkt.extend_from_slice(&1u16.to_be_bytes()); // QTYPE A
+    pkt.extend_from_slice(&1u16.to_be_bytes()); // QCLASS IN
+    pkt
+}
+
+/// Skip a DNS name at `pos`, returning the offset of the byte after it.
+/// Names in the answer section may end in a 2-byte 0xC0 compression
+/// pointer instead of a zero-length label; since callers here only need to
+/// find the *following* field, the pointer target is never followed, which
+/// also means a malformed packet can't make this loop (each step advances
+/// `pos` monotonically and the walk is bounded by `buf.len()`).
+fn skip_name(buf: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *buf.get(pos)?;
+        if len & 0xC0 == 0xC0 {
+            buf.get(pos + 1)?;
+            return Some(pos + 2);
+        }
+        if len == 0 {
+            return Some(pos + 1);
+        }
+        pos = pos.checked_add(1 + len as usize)?;
+    }
+}
+
+enum DnsParse {
+    Answer(u32),
+    Truncated,
+}
+
+/// Parse a DNS response, returning the first A/IN answer's address.
+/// Responses whose transaction id doesn't match `expected_txn_id` are
+/// treated as malformed (`None`) rather than retried here; the caller owns
+/// retry/timeout policy.
+fn parse_response(buf: &[u8], expected_txn_id: u16) -> Option<DnsParse> {
+    if buf.len() < 12 {
+        return None;
+    }
+    let txn_id = u16::from_be_bytes([buf[0], buf[1]]);
+    if txn_id != expected_txn_id {
+        return None;
+    }
+    let flags = u16::from_be_bytes([buf[2], buf[3]]);
+    let truncated = flags & 0x0200 != 0;
+    let qdcount = u16::from_be_bytes([buf[4], buf[5]]) as usize;
+    let ancount = u16::from_be_bytes([buf[6], buf[7]]) as usize;
+
+    let mut pos = 12usize;
+    for _ in 0..qdcount {
+        pos = skip_name(buf, pos)?;
+        pos = pos.checked_add(4)?; // QTYPE + QCLASS
+    }
+
+    for _ in 0..ancount {
+        pos = skip_name(buf, pos)?;
+        let rtype = u16::from_be_bytes([*buf.get(pos)?, *buf.get(pos + 1)?]);
+        let rclass = u16::from_be_bytes([*buf.get(pos + 2)?, *buf.get(pos + 3)?]);
+        // bytes pos+4..pos+8 are the TTL, not needed here
+        let rdlength = u16::from_be_bytes([*buf.get(pos + 8)?, *buf.get(pos + 9)?]) as usize;
+        let rdata_start = pos.checked_add(10)?;
+        if rtype == 1 && rclass == 1 && rdlength == 4 {
+            let a = buf.get(rdata_start..rdata_start + 4)?;
+            return Some(DnsParse::Answer(u32::from_be_bytes([a[0], a[1], a[2], a[3]])));
+        }
+        pos = rdata_start.checked_add(rdlength)?;
+    }
+
+    if truncated { Some(DnsParse::Truncated) } else { None }
+}
+
+/// Resolve `hostname`'s first A record against `resolver_addr` (an IPv4
+/// address in network byte order, e.g. `DEFAULT_RESOLVER_ADDR`). Retries
+/// over TCP, per RFC 1035 4.2.2, if the UDP reply sets the TC bit.
+pub async fn resolve_a_record(hostname: &str, resolver_addr: u32) -> Option<u32> {
+    let txn_id = next_txn_id();
+    let query = build_query(txn_id, hostname);
+    let server = inet4_sockaddr(53, resolver_addr);
+    let server_bytes = sockaddr_bytes(&server);
+
+    let fd = async_syscall::socket(AF_INET, SOCK_DGRAM, 0);
+    if fd < 0 {
+        return None;
+    }
+    let r = ConnectFuture::new(fd, server_bytes.as_ptr(), server_bytes.len()).await;
+    if r < 0 {
+        let _ = async_syscall::close(fd);
+        return None;
+    }
+    let _ = SendFuture::new(fd, &query).await;
+    let resp = RecvFuture::new(fd, 512).await;
+    let _ = async_syscall::close(fd);
+
+    match parse_response(&resp, txn_id) {
+        Some(DnsParse::Answer(addr)) => Some(addr),
+        Some(DnsParse::Truncated) => resolve_a_record_tcp(hostname, resolver_addr, txn_id).await,
+        None => None,
+    }
+}
+
+async fn resolve_a_record_tcp(hostname: &str, resolver_addr: u32, txn_id: u16) -> Option<u32> {
+    let query = build_query(txn_id, hostname);
+    let server = inet4_sockaddr(53, resolver_addr);
+    let server_bytes = sockaddr_bytes(&server);
+
+    let fd = async_syscall::socket(AF_INET, crate::SOCK_STREAM, 0);
+    if fd < 0 {
+        return None;
+    }
+    let r = ConnectFuture::new(fd, server_bytes.as_ptr(), server_bytes.len()).await;
+    if r < 0 {
+        let _ = async_syscall::close(fd);
+        return None;
+    }
+
+    let mut framed = Vec::with_capacity(2 + query.len());
+    framed.extend_from_slice(&(query.len() as u16).to_be_bytes());
+    framed.extend_from_slice(&query);
+    let _ = SendFuture::new(fd, &framed).await;
+
+    let len_buf = RecvFuture::new(fd, 2).await;
+    if len_buf.len() < 2 {
+        let _ = async_syscall::close(fd);
+        return None;
+    }
+    let resp_len = u16::from_be_bytes([len_buf[0], len_buf[1]]) as usize;
+    let resp = RecvFuture::new(fd, resp_len).await;
+    let _ = async_syscall::close(fd);
+
+    match parse_response(&resp, txn_id) {
+        Some(DnsParse::Answer(addr)) => Some(addr),
+        _ => None,
+    }
+}
@@ -1,13 +1,85 @@
-//! Minimal PTY helpers (stub)
+//! Minimal PTY helpers backed by the Linux `/dev/ptmx` ABI.
 
 #![no_std]
 
-extern crate alloc;
+use async_syscall as sys;
 
-// Provide a tiny stub for opening a pty pair. Real implementation would
-// require ioctl/TIOCGPT etc; keep as a safe stub for now.
+const O_RDWR: i32 = sys::O_RDWR;
+const O_NOCTTY: i32 = sys::O_NOCTTY;
 
+// ioctl request numbers (x86_64 Linux).
+const TIOCGPTN: u64 = 0x80045430;
+const TIOCSPTLCK: u64 = 0x40045431;
+const TIOCSWINSZ: u64 = 0x5414;
+
+const PTMX_PATH: &[u8] = b"/dev/ptmx\0";
+
+#[repr(C)]
+struct Winsize {
+    ws_row: u16,
+    ws_col: u16,
+    ws_xpixel: u16,
+    ws_ypixel: u16,
+}
+
+/// Format `/dev/pts/{num}` into a stack buffer, NUL-terminated, returning the
+/// number of bytes written including the terminator.
+fn format_pts_path(num: u32, out: &mut [u8; 32]) -> usize {
+    const PREFIX: &[u8] = b"/dev/pts/";
+    out[..PREFIX.len()].copy_from_slice(PREFIX);
+    let (digits, len) = sys::format_usize(num as usize);
+    out[PREFIX.len()..PREFIX.len() + len].copy_from_slice(&digits[..len]);
+    out[PREFIX.len() + len] = 0;
+    PREFIX.len() + len + 1
+}
+
+/// Open a new pseudo-terminal pair, returning `(master_fd, slave_fd)`.
+///
+/// Opens `/dev/ptmx` for the master side, unlocks and reads the slave
+/// number via `ioctl`, then opens `/dev/pts/{num}` for the slave side.
+/// A failing `ioctl` surfaces its negative errno as `Err(errno)`; a failing
+/// `open` of `/dev/ptmx` or of the slave surfaces as `Err(-1)`, since
+/// `sys::open` does not pass the errno through.
 pub fn openpty() -> Result<(i32, i32), i32> {
-    // Not implemented: return error
-    Err(-1)
+    let master = sys::open(PTMX_PATH.as_ptr(), O_RDWR | O_NOCTTY, 0);
+    if master < 0 {
+        return Err(-1);
+    }
+
+    let unlock: i32 = 0;
+    let r = sys::ioctl(master, TIOCSPTLCK, &unlock as *const i32 as u64);
+    if r < 0 {
+        let _ = sys::close(master);
+        return Err(r as i32);
+    }
+
+    let mut slave_num: u32 = 0;
+    let r = sys::ioctl(master, TIOCGPTN, &mut slave_num as *mut u32 as u64);
+    if r < 0 {
+        let _ = sys::close(master);
+        return Err(r as i32);
+    }
+
+    let mut path_buf = [0u8; 32];
+    format_pts_path(slave_num, &mut path_buf);
+    let slave = sys::open(path_buf.as_ptr(), O_RDWR | O_NOCTTY, 0);
+    if slave < 0 {
+        let _ = sys::close(master);
+        return Err(-1);
+    }
+
+    Ok((master, slave))
+}
+
+/// Put `fd` into non-blocking mode so it can drive `RecvFuture`/`SendFuture`.
+pub fn set_nonblocking(fd: i32) -> Result<(), i32> {
+    let r = sys::fcntl(fd, sys::F_SETFL, sys::O_NONBLOCK);
+    if r < 0 { Err(r as i32) } else { Ok(()) }
+}
+
+/// Forward a terminal resize to the pty via `TIOCSWINSZ`.
+pub fn set_winsize(fd: i32, rows: u16, cols: u16) -> Result<(), i32> {
+    let ws = Winsize { ws_row: rows, ws_col: cols, ws_xpixel: 0, ws_ypixel: 0 };
+    let r = sys::ioctl(fd, TIOCSWINSZ, &ws as *const Winsize as u64);
+    if r < 0 { Err(r as i32) } else { Ok(()) }
 }
@@ -1,26 +1,197 @@
-//! Bump allocator - simple, fast, no deallocation
+//! First-fit free-list allocator with boundary-tag coalescing
+//!
+//! Every live block (free or in use) is laid out as:
+//!
+//!   [ header: usize = payload size ][ payload... ][ footer: usize = payload size ]
+//!
+//! padded so the block is always 16 bytes wide before and after the
+//! payload. The header lets `dealloc` recover a block's extent from just
+//! the user pointer; the footer lets the block immediately *before* a freed
+//! block be located by address arithmetic (`addr - FOOTER_SIZE`) so forward
+//! and backward neighbors can both be coalesced on free.
 
 use crate::config::HEAP_SIZE;
 use crate::syscall;
 use core::alloc::{GlobalAlloc, Layout};
 use core::sync::atomic::{AtomicUsize, Ordering};
+
 static HEAP_START: AtomicUsize = AtomicUsize::new(0);
 static HEAP_CUR: AtomicUsize = AtomicUsize::new(0);
 static HEAP_END: AtomicUsize = AtomicUsize::new(0);
 
+const HEADER_SIZE: usize = 16;
+const FOOTER_SIZE: usize = 16;
+// A free block's payload doubles as storage for `FreeBlock { size, next }`,
+// so it must be at least that wide.
+const MIN_PAYLOAD: usize = 16;
+
+struct FreeBlock {
+    size: usize,
+    next: *mut FreeBlock,
+}
+
+static FREE_LIST: spin::Mutex<*mut FreeBlock> = spin::Mutex::new(core::ptr::null_mut());
+
+fn init_heap() {
+    if HEAP_START.load(Ordering::Relaxed) == 0 {
+        let ptr = syscall::mmap(0, HEAP_SIZE, 3, 0x22);
+        if !ptr.is_null() {
+            let addr = align_up(ptr as usize, 16);
+            HEAP_START.store(addr, Ordering::Relaxed);
+            HEAP_CUR.store(addr, Ordering::Relaxed);
+            HEAP_END.store(ptr as usize + HEAP_SIZE, Ordering::Relaxed);
+        }
+    }
+}
+
+#[inline]
+fn align_up(x: usize, align: usize) -> usize {
+    (x + align - 1) & !(align - 1)
+}
+
+unsafe fn write_header_footer(block_addr: usize, payload_size: usize) {
+    unsafe {
+        *(block_addr as *mut usize) = payload_size;
+        *((block_addr + HEADER_SIZE + payload_size) as *mut usize) = payload_size;
+    }
+}
+
+fn bump_alloc(payload_size: usize) -> Option<usize> {
+    loop {
+        let block_addr = HEAP_CUR.load(Ordering::Relaxed);
+        let total = HEADER_SIZE + payload_size + FOOTER_SIZE;
+        let next = block_addr.checked_add(total)?;
+        if next > HEAP_END.load(Ordering::Relaxed) {
+            return None;
+        }
+        if HEAP_CUR
+            .compare_exchange(block_addr, next, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+        {
+            return Some(block_addr);
+        }
+    }
+}
+
+/// Push `block_addr` (payload size `payload_size`) onto the free list,
+/// (re)writing its header/footer.
+fn push_free(list: &mut *mut FreeBlock, block_addr: usize, payload_size: usize) {
+    unsafe { write_header_footer(block_addr, payload_size) };
+    let node = (block_addr + HEADER_SIZE) as *mut FreeBlock;
+    unsafe {
+        (*node).size = payload_size;
+        (*node).next = *list;
+    }
+    *list = node;
+}
+
+/// Remove the free block whose header starts at `block_addr`, if present.
+fn remove_free_at(list: &mut *mut FreeBlock, block_addr: usize) -> Option<usize> {
+    let mut prev: *mut FreeBlock = core::ptr::null_mut();
+    let mut cur = *list;
+    while !cur.is_null() {
+        let cur_block_addr = cur as usize - HEADER_SIZE;
+        if cur_block_addr == block_addr {
+            let size = unsafe { (*cur).size };
+            let next = unsafe { (*cur).next };
+            if prev.is_null() {
+                *list = next;
+            } else {
+                unsafe { (*prev).next = next };
+            }
+            return Some(size);
+        }
+        prev = cur;
+        cur = unsafe { (*cur).next };
+    }
+    None
+}
+
+/// First-fit search of the free list; splits the tail back onto the list
+/// when the match is large enough to host another block.
+fn take_free_block(payload_size: usize) -> Option<usize> {
+    let mut list = FREE_LIST.lock();
+    let mut prev: *mut FreeBlock = core::ptr::null_mut();
+    let mut cur = *list;
+    while !cur.is_null() {
+        let blk_size = unsafe { (*cur).size };
+        if blk_size >= payload_size {
+            let next = unsafe { (*cur).next };
+            if prev.is_null() {
+                *list = next;
+            } else {
+                unsafe { (*prev).next = next };
+            }
+            let block_addr = cur as usize - HEADER_SIZE;
+            let remaining = blk_size - payload_size;
+            if remaining >= HEADER_SIZE + FOOTER_SIZE + MIN_PAYLOAD {
+                let tail_payload = remaining - HEADER_SIZE - FOOTER_SIZE;
+                let tail_addr = block_addr + HEADER_SIZE + payload_size + FOOTER_SIZE;
+                push_free(&mut list, tail_addr, tail_payload);
+                unsafe { write_header_footer(block_addr, payload_size) };
+            } else {
+                unsafe { write_header_footer(block_addr, blk_size) };
+            }
+            return Some(block_addr);
+        }
+        prev = cur;
+        cur = unsafe { (*cur).next };
+    }
+    None
+}
+
+unsafe fn dealloc_block(user_ptr: *mut u8) {
+    let mut addr = user_ptr as usize - HEADER_SIZE;
+    let mut payload_size = unsafe { *(addr as *const usize) };
+
+    let heap_start = HEAP_START.load(Ordering::Relaxed);
+    let heap_cur = HEAP_CUR.load(Ordering::Relaxed);
+
+    let mut list = FREE_LIST.lock();
+
+    // Coalesce forward with the block(s) immediately following us.
+    loop {
+        let next_addr = addr + HEADER_SIZE + payload_size + FOOTER_SIZE;
+        if next_addr >= heap_cur {
+            break;
+        }
+        match remove_free_at(&mut list, next_addr) {
+            Some(next_payload) => payload_size += HEADER_SIZE + FOOTER_SIZE + next_payload,
+            None => break,
+        }
+    }
+
+    // Coalesce backward with the block immediately preceding us: its
+    // footer (just before our header) records its payload size.
+    loop {
+        if addr <= heap_start {
+            break;
+        }
+        let prev_payload = unsafe { *((addr - FOOTER_SIZE) as *const usize) };
+        let prev_block_addr = match addr
+            .checked_sub(HEADER_SIZE + FOOTER_SIZE)
+            .and_then(|a| a.checked_sub(prev_payload))
+        {
+            Some(a) if a >= heap_start => a,
+            _ => break,
+        };
+        match remove_free_at(&mut list, prev_block_addr) {
+            Some(_) => {
+                payload_size += HEADER_SIZE + FOOTER_SIZE + prev_payload;
+                addr = prev_block_addr;
+            }
+            None => break,
+        }
+    }
+
+    push_free(&mut list, addr, payload_size);
+}
+
 pub struct BumpAllocator;
 
 unsafe impl GlobalAlloc for BumpAllocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        if HEAP_START.load(Ordering::Relaxed) == 0 {
-            let ptr = syscall::mmap(0, HEAP_SIZE, 3, 0x22);
-            if !ptr.is_null() {
-                let addr = ptr as usize;
-                HEAP_START.store(addr, Ordering::Relaxed);
-                HEAP_CUR.store(addr, Ordering::Relaxed);
-                HEAP_END.store(addr + HEAP_SIZE, Ordering::Relaxed);
-            }
-        }
+        init_heap();
 
         let align = layout.align().max(1);
         let size = layout.size();
@@ -28,25 +199,36 @@ unsafe impl GlobalAlloc for BumpAllocator {
             return align as *mut u8;
         }
 
-        loop {
-            let cur = HEAP_CUR.load(Ordering::Relaxed);
-            let aligned = (cur + align - 1) & !(align - 1);
-            let next = match aligned.checked_add(size) {
-                Some(n) => n,
-                None => return core::ptr::null_mut(),
-            };
-            if next > HEAP_END.load(Ordering::Relaxed) {
-                return core::ptr::null_mut();
-            }
-            if HEAP_CUR
-                .compare_exchange(cur, next, Ordering::Relaxed, Ordering::Relaxed)
-                .is_ok()
-            {
-                return aligned as *mut u8;
+        if align > HEADER_SIZE {
+            // Rare large-alignment request: hand out a dedicated mmap
+            // region instead of complicating the block scheme for it.
+            // `dealloc` recognizes this case from the same `layout` and
+            // intentionally leaves it unreclaimed.
+            let len = align_up(size, 4096);
+            return syscall::mmap(0, len, 3, 0x22);
+        }
+
+        let payload_size = align_up(size, 16).max(MIN_PAYLOAD);
+
+        if let Some(block_addr) = take_free_block(payload_size) {
+            return (block_addr + HEADER_SIZE) as *mut u8;
+        }
+
+        match bump_alloc(payload_size) {
+            Some(block_addr) => {
+                unsafe { write_header_footer(block_addr, payload_size) };
+                (block_addr + HEADER_SIZE) as *mut u8
             }
+            None => core::ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if layout.size() == 0 || layout.align() > HEADER_SIZE {
+            return;
         }
+        unsafe { dealloc_block(ptr) };
     }
-    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
 }
 
 #[global_allocator]
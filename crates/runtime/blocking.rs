@@ -0,0 +1,103 @@
+//! Dedicated thread pool for blocking work (a long file read, a DNS lookup,
+//! heavy compute) kept off the worker threads that also service I/O via
+//! `ppoll_and_schedule` - one blocking job on a regular worker would stall
+//! every task waiting on that reactor turn.
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use async_syscall as sys;
+use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+
+use crate::config::MAX_BLOCKING_THREADS;
+use crate::scheduler::{self, JoinHandle};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static JOB_QUEUE: spin::Mutex<VecDeque<Job>> = spin::Mutex::new(VecDeque::new());
+static POOL_EVENTFD: AtomicI32 = AtomicI32::new(-1);
+static THREADS_STARTED: AtomicUsize = AtomicUsize::new(0);
+static POOL_LOCK: spin::Mutex<()> = spin::Mutex::new(());
+
+/// Each `write(1)` wakes exactly one blocked reader instead of handing the
+/// whole accumulated counter to a single thread - the right eventfd mode
+/// for a job-queue semaphore shared by several idle pool threads.
+const EFD_SEMAPHORE: i32 = 1;
+
+fn pool_eventfd() -> i32 {
+    let existing = POOL_EVENTFD.load(Ordering::Acquire);
+    if existing >= 0 {
+        return existing;
+    }
+    let _guard = POOL_LOCK.lock();
+    let existing = POOL_EVENTFD.load(Ordering::Acquire);
+    if existing >= 0 {
+        return existing;
+    }
+    let fd = sys::eventfd(0, EFD_SEMAPHORE);
+    POOL_EVENTFD.store(fd, Ordering::Release);
+    fd
+}
+
+extern "C" fn blocking_worker(_arg: *mut u8) {
+    let evfd = pool_eventfd();
+    loop {
+        match JOB_QUEUE.lock().pop_front() {
+            Some(job) => job(),
+            None => {
+                // Park on the semaphore eventfd until a job is queued,
+                // rather than spin-polling the queue.
+                let mut buf = [0u8; 8];
+                let _ = sys::read(evfd, &mut buf);
+            }
+        }
+    }
+}
+
+fn ensure_pool_started() {
+    if THREADS_STARTED.load(Ordering::Acquire) > 0 {
+        return;
+    }
+    let _guard = POOL_LOCK.lock();
+    if THREADS_STARTED.load(Ordering::Acquire) > 0 {
+        return;
+    }
+    pool_eventfd(); // must exist before any pool thread can park on it
+    for _ in 0..MAX_BLOCKING_THREADS {
+        let _ = sys::spawn_thread(
+            blocking_worker,
+            core::ptr::null_mut(),
+            crate::config::WORKER_STACK_SIZE,
+        );
+    }
+    THREADS_STARTED.store(MAX_BLOCKING_THREADS, Ordering::Release);
+}
+
+/// Run `f` on the blocking thread pool and return a [`JoinHandle`] that
+/// resolves to its result. Unlike [`crate::spawn`], `f` is a plain closure
+/// run to completion on a dedicated OS thread, not a future polled on a
+/// normal worker - use this for calls that would otherwise block the
+/// reactor (file I/O without an async wrapper, DNS, heavy compute).
+pub fn spawn_blocking<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    ensure_pool_started();
+
+    let state = scheduler::new_join_state::<T>();
+    // Raw pointers aren't Send; round-trip through a usize to carry it into
+    // the boxed closure (same trick `coop` uses to carry a waker's handle
+    // across its raw-pointer boundary).
+    let state_addr = state as usize;
+    let job: Job = Box::new(move || {
+        let value = f();
+        let state = state_addr as *const scheduler::JoinState<T>;
+        scheduler::fulfill_join_state(state, value);
+    });
+
+    JOB_QUEUE.lock().push_back(job);
+    let evfd = pool_eventfd();
+    let _ = sys::write(evfd, &1u64.to_ne_bytes());
+
+    scheduler::join_handle_from_raw(state)
+}
@@ -0,0 +1,342 @@
+//! Bounded MPMC channel for inter-task handoff (e.g. `accept_loop` handing
+//! connections to a worker pool instead of unconditionally spawning).
+//!
+//! The ring buffer itself is the classic Vyukov bounded MPMC queue: each slot
+//! carries a `stamp` alongside its value. A send claims the tail slot once
+//! its stamp matches the tail position, writes the value, then bumps the
+//! stamp to `pos + 1` to publish it to receivers. A receive does the
+//! symmetric wait for `pos + 1` at the head slot and republishes the slot for
+//! the next lap by bumping the stamp to `pos + capacity`. No locks are held
+//! on the fast path - only a `compare_exchange_weak` race on `head`/`tail`.
+//!
+//! When the buffer is full or empty the operation parks the caller's
+//! `Waker` in a small `spin::Mutex<Vec<Waker>>` (one list per direction) and
+//! returns `Pending`; the complementary operation wakes one parked waker
+//! after it frees or fills a slot.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::future::Future;
+use core::mem::MaybeUninit;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::task::{Context, Poll, Waker};
+
+struct Slot<T> {
+    stamp: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+struct Inner<T> {
+    buffer: Box<[Slot<T>]>,
+    cap: usize,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    senders_alive: AtomicUsize,
+    receivers_alive: AtomicUsize,
+    /// Combined count of every live `Sender`/`Receiver` handle, distinct from
+    /// `senders_alive`/`receivers_alive` above (which only track each side's
+    /// own close detection). `Inner` is freed by whichever `Drop` observes
+    /// this hit zero via `fetch_sub`, so the last sender and last receiver
+    /// dropping concurrently on different threads can't both decide they're
+    /// the one to free it.
+    handles_alive: AtomicUsize,
+    send_wakers: spin::Mutex<Vec<Waker>>,
+    recv_wakers: spin::Mutex<Vec<Waker>>,
+}
+
+impl<T> Inner<T> {
+    /// Try to claim the tail slot and write `value`. `Ok(())` on success,
+    /// `Err(value)` if the buffer is currently full.
+    fn try_send(&self, value: T) -> Result<(), T> {
+        let mut pos = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos % self.cap];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+            let diff = stamp as isize - pos as isize;
+            if diff == 0 {
+                match self.tail.compare_exchange_weak(
+                    pos,
+                    pos + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        unsafe { (*slot.value.get()).write(value) };
+                        slot.stamp.store(pos + 1, Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(cur) => pos = cur,
+                }
+            } else if diff < 0 {
+                return Err(value);
+            } else {
+                pos = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Try to claim the head slot and take its value. `None` if the buffer
+    /// is currently empty.
+    fn try_recv(&self) -> Option<T> {
+        let mut pos = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[pos % self.cap];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+            let diff = stamp as isize - (pos + 1) as isize;
+            if diff == 0 {
+                match self.head.compare_exchange_weak(
+                    pos,
+                    pos + 1,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let value = unsafe { (*slot.value.get()).assume_init_read() };
+                        slot.stamp.store(pos + self.cap, Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(cur) => pos = cur,
+                }
+            } else if diff < 0 {
+                return None;
+            } else {
+                pos = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn wake_one(list: &spin::Mutex<Vec<Waker>>) {
+    if let Some(w) = list.lock().pop() {
+        w.wake();
+    }
+}
+
+fn wake_all(list: &spin::Mutex<Vec<Waker>>) {
+    for w in list.lock().drain(..) {
+        w.wake();
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        // Anything still buffered between head and tail was never taken out
+        // through `try_recv` and needs its destructor run explicitly.
+        while self.try_recv().is_some() {}
+    }
+}
+
+/// Create a bounded MPMC channel with room for `cap` in-flight values.
+pub fn channel<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(cap > 0, "channel capacity must be nonzero");
+    let buffer: Box<[Slot<T>]> = (0..cap)
+        .map(|i| Slot { stamp: AtomicUsize::new(i), value: UnsafeCell::new(MaybeUninit::uninit()) })
+        .collect();
+    let inner = Box::into_raw(Box::new(Inner {
+        buffer,
+        cap,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        senders_alive: AtomicUsize::new(1),
+        receivers_alive: AtomicUsize::new(1),
+        handles_alive: AtomicUsize::new(2),
+        send_wakers: spin::Mutex::new(Vec::new()),
+        recv_wakers: spin::Mutex::new(Vec::new()),
+    }));
+    (Sender { inner }, Receiver { inner })
+}
+
+/// Returned by [`Sender::send`] when every [`Receiver`] has been dropped;
+/// carries the value back so the caller can decide what to do with it.
+pub struct SendError<T>(pub T);
+
+/// The sending half of a channel created by [`channel`]. Cloneable - any
+/// number of tasks may hold a `Sender` for the same channel.
+pub struct Sender<T> {
+    inner: *const Inner<T>,
+}
+
+unsafe impl<T: Send> Send for Sender<T> {}
+unsafe impl<T: Send> Sync for Sender<T> {}
+
+impl<T> Sender<T> {
+    fn inner(&self) -> &Inner<T> {
+        unsafe { &*self.inner }
+    }
+
+    /// Send `value`, waiting for room if the channel is currently full.
+    /// Resolves to `Err(SendError(value))` if every `Receiver` has already
+    /// been dropped.
+    pub fn send(&self, value: T) -> SendFuture<'_, T> {
+        SendFuture { sender: self, value: Some(value) }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.inner().senders_alive.fetch_add(1, Ordering::Relaxed);
+        self.inner().handles_alive.fetch_add(1, Ordering::Relaxed);
+        Self { inner: self.inner }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let inner = self.inner();
+        if inner.senders_alive.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // Last sender gone - parked receivers need a chance to observe
+            // the close once the buffer is drained.
+            wake_all(&inner.recv_wakers);
+        }
+        // Whichever drop (this one or the last Receiver's) observes
+        // handles_alive hit zero is the one that frees Inner - a single
+        // fetch_sub, not two independently-loaded counters, so the last
+        // Sender and last Receiver dropping concurrently can't both free it.
+        if inner.handles_alive.fetch_sub(1, Ordering::AcqRel) == 1 {
+            unsafe { drop(Box::from_raw(self.inner as *mut Inner<T>)) };
+        }
+    }
+}
+
+/// Future returned by [`Sender::send`].
+pub struct SendFuture<'a, T> {
+    sender: &'a Sender<T>,
+    value: Option<T>,
+}
+
+impl<'a, T> Future for SendFuture<'a, T> {
+    type Output = Result<(), SendError<T>>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if !async_runtime_poll_proceed(cx) {
+            return Poll::Pending;
+        }
+        // Plain owned fields, nothing address-sensitive to preserve - no
+        // need to require `T: Unpin` just to get a mutable reference.
+        let this = unsafe { self.get_unchecked_mut() };
+        let inner = this.sender.inner();
+        let value = this.value.take().expect("SendFuture polled after completion");
+        if inner.receivers_alive.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(Err(SendError(value)));
+        }
+        let value = match inner.try_send(value) {
+            Ok(()) => {
+                wake_one(&inner.recv_wakers);
+                return Poll::Ready(Ok(()));
+            }
+            Err(value) => value,
+        };
+        if inner.receivers_alive.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(Err(SendError(value)));
+        }
+        inner.send_wakers.lock().push(cx.waker().clone());
+        // A receiver may have drained a slot (or dropped) between the
+        // failed try_send above and parking the waker.
+        match inner.try_send(value) {
+            Ok(()) => {
+                wake_one(&inner.recv_wakers);
+                Poll::Ready(Ok(()))
+            }
+            Err(value) => {
+                if inner.receivers_alive.load(Ordering::Acquire) == 0 {
+                    Poll::Ready(Err(SendError(value)))
+                } else {
+                    this.value = Some(value);
+                    Poll::Pending
+                }
+            }
+        }
+    }
+}
+
+/// The receiving half of a channel created by [`channel`]. Cloneable - any
+/// number of tasks may hold a `Receiver` for the same channel, competing for
+/// each value.
+pub struct Receiver<T> {
+    inner: *const Inner<T>,
+}
+
+unsafe impl<T: Send> Send for Receiver<T> {}
+unsafe impl<T: Send> Sync for Receiver<T> {}
+
+impl<T> Receiver<T> {
+    fn inner(&self) -> &Inner<T> {
+        unsafe { &*self.inner }
+    }
+
+    /// Receive the next value, waiting if the channel is currently empty.
+    /// Resolves to `None` once the channel is drained and every `Sender`
+    /// has been dropped.
+    pub fn recv(&self) -> RecvFuture<'_, T> {
+        RecvFuture { receiver: self }
+    }
+
+    fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let inner = self.inner();
+        if let Some(value) = inner.try_recv() {
+            wake_one(&inner.send_wakers);
+            return Poll::Ready(Some(value));
+        }
+        if inner.senders_alive.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(None);
+        }
+        inner.recv_wakers.lock().push(cx.waker().clone());
+        // A sender may have published a value (or dropped) between the
+        // failed try_recv above and parking the waker.
+        if let Some(value) = inner.try_recv() {
+            wake_one(&inner.send_wakers);
+            return Poll::Ready(Some(value));
+        }
+        if inner.senders_alive.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.inner().receivers_alive.fetch_add(1, Ordering::Relaxed);
+        self.inner().handles_alive.fetch_add(1, Ordering::Relaxed);
+        Self { inner: self.inner }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let inner = self.inner();
+        if inner.receivers_alive.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // Last receiver gone - parked senders need to learn that
+            // nothing will ever drain the channel again.
+            wake_all(&inner.send_wakers);
+        }
+        // See the matching comment in `Sender::drop` - freeing on this
+        // shared counter's fetch_sub (rather than re-checking both alive
+        // counters) is what makes concurrent last-sender/last-receiver drops
+        // safe.
+        if inner.handles_alive.fetch_sub(1, Ordering::AcqRel) == 1 {
+            unsafe { drop(Box::from_raw(self.inner as *mut Inner<T>)) };
+        }
+    }
+}
+
+/// Future returned by [`Receiver::recv`].
+pub struct RecvFuture<'a, T> {
+    receiver: &'a Receiver<T>,
+}
+
+impl<'a, T> Future for RecvFuture<'a, T> {
+    type Output = Option<T>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if !async_runtime_poll_proceed(cx) {
+            return Poll::Pending;
+        }
+        self.receiver.poll_recv(cx)
+    }
+}
+
+#[inline]
+fn async_runtime_poll_proceed(cx: &Context<'_>) -> bool {
+    crate::coop::poll_proceed(cx)
+}
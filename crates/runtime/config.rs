@@ -30,3 +30,10 @@ pub const LOG_FILE_PATH: &[u8] = b"/tmp/async-nostd.log\0";
 
 /// Socket listen backlog
 pub const LISTEN_BACKLOG: i32 = 128;
+
+/// Capacity of each worker's local run queue before `wake_handle` overflows
+/// onto the global injection stack.
+pub const LOCAL_QUEUE_CAP: usize = 256;
+
+/// Number of OS threads in the dedicated blocking-task pool (`spawn_blocking`).
+pub const MAX_BLOCKING_THREADS: usize = 4;
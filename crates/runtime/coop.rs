@@ -0,0 +1,39 @@
+//! Cooperative scheduling budget (Tokio coop-style), bounding how much work
+//! a single task poll can perform before yielding back to other scheduled
+//! tasks. A hot, always-ready future (e.g. a socket with endless data)
+//! would otherwise monopolize its worker forever.
+//!
+//! The budget lives in this worker thread's TLS slot rather than a shared
+//! atomic, since it tracks "how much has *this* worker polled since its
+//! last dequeue" - a global counter would have unrelated workers tripping
+//! each other's budgets.
+
+use crate::scheduler;
+use async_syscall as syscall;
+use core::task::Context;
+
+/// Fresh budget granted each time a worker dequeues a new handle to poll.
+const BUDGET_QUOTA: usize = 128;
+
+/// Reset this worker's budget to a full quota. Called from
+/// `take_scheduled_task` whenever it hands back a fresh handle.
+pub fn reset_budget() {
+    syscall::set_tls_word(0, BUDGET_QUOTA);
+}
+
+/// Consume one unit of this worker's poll budget. The runtime's own I/O
+/// leaf futures call this before doing real work; once the budget is
+/// exhausted this re-schedules the current task itself and returns `false`,
+/// and the caller just returns `Pending`, so other scheduled tasks get a
+/// turn on this worker rather than being starved by one hot, always-ready
+/// future.
+pub fn poll_proceed(cx: &Context<'_>) -> bool {
+    let remaining = syscall::tls_word(0);
+    if remaining == 0 {
+        let handle = cx.waker().as_raw().data() as usize;
+        scheduler::wake_handle(handle);
+        return false;
+    }
+    syscall::set_tls_word(0, remaining - 1);
+    true
+}
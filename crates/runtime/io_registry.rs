@@ -1,18 +1,96 @@
 //! IO event registry for async file descriptor operations
+//!
+//! Each fd gets one `ScheduledIo` slot (modeled on Tokio's io driver) instead
+//! of a single shared waiter list: a readiness bitset plus a separate waker
+//! per direction, so a task parked on read and a task parked on write for
+//! the same fd are woken independently instead of both firing on any event.
+//!
+//! Dispatch is backed by a single persistent `epoll` instance rather than
+//! rebuilding and rescanning a `pollfd` array every wakeup: each fd is
+//! registered edge-triggered and one-shot (`EPOLLET | EPOLLONESHOT`),
+//! carrying its slab index in `epoll_event.data`, so `epoll_wait` hands back
+//! only the fds that are actually ready and the reactor indexes straight
+//! into the slab instead of walking every registered fd. A wait on an fd
+//! whose slot is still live re-arms it via `epoll_ctl(EPOLL_CTL_MOD)`; once
+//! a delivery leaves no waker parked, the slot and the fd's epoll
+//! registration are dropped and the next wait adds it afresh
+//! (`EPOLL_CTL_ADD`).
 
 use crate::syscall;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
 use core::task::Waker;
 
-pub struct IoEntry {
+/// Interest/readiness bits, matching the `POLLIN`/`POLLOUT` masks already
+/// used by the socket futures (and numerically identical to the
+/// corresponding `EPOLLIN`/`EPOLLOUT` bits).
+pub const READABLE: i16 = 0x0001;
+pub const WRITABLE: i16 = 0x0004;
+/// EPOLLERR | EPOLLHUP - fd is gone; treated as ready in both directions so
+/// every waiter observes it.
+pub const CLOSED: i16 = 0x0018;
+
+/// Sentinel `data` value for the eventfd's epoll registration, distinct from
+/// any real slab index.
+const EVENTFD_DATA: u64 = u64::MAX;
+
+/// Edge-triggered, one-shot: the kernel reports a transition to ready exactly
+/// once and then disarms the fd, so a waiter that doesn't immediately drain
+/// the fd (or that never comes back) can't cause `epoll_wait` to keep waking
+/// every worker with the same stale event every turn. `register_fd_waker`
+/// re-arms explicitly each time a new waker is parked (`EPOLL_CTL_MOD`, or
+/// `EPOLL_CTL_ADD` once the slot has been dropped), and
+/// `mark_ready_and_take_wakers` re-arms for any waker still parked.
+const EPOLLET: u32 = 1 << 31;
+const EPOLLONESHOT: u32 = 1 << 30;
+
+pub struct ScheduledIo {
     pub fd: i32,
-    pub events: i16,
-    pub waiters: Vec<Waker>,
+    /// Bits the reactor has observed ready since the last clear.
+    pub readiness: i16,
+    read_waker: Option<Waker>,
+    write_waker: Option<Waker>,
 }
 
-pub static IO_REG: spin::Mutex<Vec<IoEntry>> = spin::Mutex::new(Vec::new());
-pub static EVENTFD: AtomicI32 = AtomicI32::new(-1);
+/// Slab of `ScheduledIo` slots. Indices are stable across removals (freed
+/// slots are reused via `free`), since a live `epoll_event.data` elsewhere
+/// in the kernel's interest list refers to a slot by index.
+struct Slab {
+    slots: Vec<Option<ScheduledIo>>,
+    free: Vec<usize>,
+}
+
+impl Slab {
+    const fn new() -> Self {
+        Self { slots: Vec::new(), free: Vec::new() }
+    }
+
+    fn insert(&mut self, io: ScheduledIo) -> usize {
+        if let Some(idx) = self.free.pop() {
+            self.slots[idx] = Some(io);
+            idx
+        } else {
+            self.slots.push(Some(io));
+            self.slots.len() - 1
+        }
+    }
+
+    fn find_by_fd(&self, fd: i32) -> Option<usize> {
+        self.slots.iter().position(|s| matches!(s, Some(e) if e.fd == fd))
+    }
+
+    fn remove(&mut self, idx: usize) -> Option<ScheduledIo> {
+        let io = self.slots[idx].take();
+        if io.is_some() {
+            self.free.push(idx);
+        }
+        io
+    }
+}
+
+static SLAB: spin::Mutex<Slab> = spin::Mutex::new(Slab::new());
+static EPOLL_FD: AtomicI32 = AtomicI32::new(-1);
+static EVENTFD: AtomicI32 = AtomicI32::new(-1);
 pub static EVENT_PENDING: AtomicUsize = AtomicUsize::new(0);
 
 pub fn ensure_eventfd() -> i32 {
@@ -51,27 +129,170 @@ pub fn close_eventfd() {
     }
 }
 
-pub fn register_fd_waker(fd: i32, events: i16, waker: Waker) {
-    let mut reg = IO_REG.lock();
-    for e in reg.iter_mut() {
-        if e.fd == fd {
-            e.waiters.push(waker);
-            return;
+/// Lazily create the epoll instance and register the eventfd (the task-wake
+/// channel) with it once. Called on every reactor turn; cheap after the
+/// first call since it's just an atomic load.
+pub fn ensure_epoll_fd() -> i32 {
+    let cur = EPOLL_FD.load(Ordering::Relaxed);
+    if cur >= 0 {
+        return cur;
+    }
+    let epfd = syscall::epoll_create1(0);
+    if epfd < 0 {
+        return -1;
+    }
+    if EPOLL_FD
+        .compare_exchange(-1, epfd, Ordering::Relaxed, Ordering::Relaxed)
+        .is_err()
+    {
+        let _ = syscall::close(epfd);
+        return EPOLL_FD.load(Ordering::Relaxed);
+    }
+    let evt = ensure_eventfd();
+    if evt >= 0 {
+        let mut ev = syscall::EpollEvent { events: READABLE as u32, data: EVENTFD_DATA };
+        let _ = syscall::epoll_ctl(epfd, syscall::EPOLL_CTL_ADD, evt, &mut ev);
+    }
+    epfd
+}
+
+/// `ev.data` value identifying the eventfd registration, for the reactor to
+/// special-case when draining `epoll_wait` results.
+pub fn eventfd_data() -> u64 {
+    EVENTFD_DATA
+}
+
+/// Register `waker` to be woken the next time `fd` becomes ready for
+/// `interest` (`READABLE` and/or `WRITABLE`). Each direction keeps its own
+/// slot, so registering for read does not clobber a waiter registered for
+/// write on the same fd. An fd with no live slot is added to the epoll
+/// interest list (`EPOLL_CTL_ADD`); an fd whose slot is live is re-armed via
+/// `EPOLL_CTL_MOD`, since `EPOLLONESHOT` disarms it after every delivery.
+/// The armed `events` mask only ever covers directions with a waker
+/// actually parked on this slot - arming a direction nobody is waiting on
+/// would spend the fd's one-shot on an event nothing consumes, leaving it
+/// disarmed with no further `register_fd_waker` call to re-arm it.
+pub fn register_fd_waker(fd: i32, interest: i16, waker: Waker) {
+    let epfd = ensure_epoll_fd();
+    let mut slab = SLAB.lock();
+    let (idx, op) = match slab.find_by_fd(fd) {
+        Some(i) => (i, syscall::EPOLL_CTL_MOD),
+        None => {
+            let idx = slab.insert(ScheduledIo { fd, readiness: 0, read_waker: None, write_waker: None });
+            (idx, syscall::EPOLL_CTL_ADD)
         }
+    };
+    let entry = slab.slots[idx].as_mut().unwrap();
+    if interest & READABLE != 0 {
+        entry.read_waker = Some(waker.clone());
+    }
+    if interest & WRITABLE != 0 {
+        entry.write_waker = Some(waker);
+    }
+    // Arm only the directions that actually have a waker parked, not an
+    // unconditional READABLE|WRITABLE - with EPOLLONESHOT the other
+    // direction's event would fire once, find no waker, and leave the fd
+    // disarmed with nothing left to re-arm it, hanging whichever waiter is
+    // still parked on the real direction of interest.
+    let mut events = EPOLLET | EPOLLONESHOT;
+    if entry.read_waker.is_some() {
+        events |= READABLE as u32;
+    }
+    if entry.write_waker.is_some() {
+        events |= WRITABLE as u32;
+    }
+    if epfd >= 0 {
+        let mut ev = syscall::EpollEvent { events, data: idx as u64 };
+        let _ = syscall::epoll_ctl(epfd, op, fd, &mut ev);
     }
-    let v = alloc::vec![waker];
-    reg.push(IoEntry {
-        fd,
-        events,
-        waiters: v,
-    });
 }
 
 pub fn unregister_fd(fd: i32) {
-    let mut reg = IO_REG.lock();
-    reg.retain(|e| e.fd != fd);
-    drop(reg); // Release lock before signal
+    let epfd = EPOLL_FD.load(Ordering::Relaxed);
+    let mut slab = SLAB.lock();
+    if let Some(idx) = slab.find_by_fd(fd) {
+        slab.remove(idx);
+        if epfd >= 0 {
+            let mut ev = syscall::EpollEvent { events: 0, data: 0 };
+            let _ = syscall::epoll_ctl(epfd, syscall::EPOLL_CTL_DEL, fd, &mut ev);
+        }
+    }
+    drop(slab);
 
-    // Signal eventfd to wake up ppoll and refresh fd list
+    // Signal eventfd to wake up a worker blocked in epoll_wait.
     signal_eventfd();
 }
+
+/// Record that the slot at `idx` observed `revents` and wake only the
+/// waker(s) whose direction intersects it - a read-ready event never wakes
+/// a write waiter and vice versa; `CLOSED` wakes both. The slot is freed
+/// once neither direction has a waker left to fire.
+pub fn mark_ready_and_take_wakers(idx: usize, revents: i16) -> Vec<Waker> {
+    let mut to_wake = Vec::new();
+    let mut slab = SLAB.lock();
+    let Some(entry) = slab.slots.get_mut(idx).and_then(|s| s.as_mut()) else {
+        return to_wake;
+    };
+    entry.readiness |= revents;
+    if revents & (READABLE | CLOSED) != 0 {
+        if let Some(w) = entry.read_waker.take() {
+            to_wake.push(w);
+        }
+    }
+    if revents & (WRITABLE | CLOSED) != 0 {
+        if let Some(w) = entry.write_waker.take() {
+            to_wake.push(w);
+        }
+    }
+    if entry.read_waker.is_none() && entry.write_waker.is_none() {
+        let fd = entry.fd;
+        slab.remove(idx);
+        drop(slab);
+        // The kernel drops the epoll registration automatically on close,
+        // but the fd may still be open and simply idle - remove it
+        // explicitly so a stale slab index never lingers in the interest
+        // list.
+        let epfd = EPOLL_FD.load(Ordering::Relaxed);
+        if epfd >= 0 {
+            let mut ev = syscall::EpollEvent { events: 0, data: 0 };
+            let _ = syscall::epoll_ctl(epfd, syscall::EPOLL_CTL_DEL, fd, &mut ev);
+        }
+    } else {
+        // EPOLLONESHOT disarmed the fd on this delivery; re-arm it for the
+        // direction still parked so that waiter is not left hanging.
+        let mut events = EPOLLET | EPOLLONESHOT;
+        if entry.read_waker.is_some() {
+            events |= READABLE as u32;
+        }
+        if entry.write_waker.is_some() {
+            events |= WRITABLE as u32;
+        }
+        let fd = entry.fd;
+        let epfd = EPOLL_FD.load(Ordering::Relaxed);
+        if epfd >= 0 {
+            let mut ev = syscall::EpollEvent { events, data: idx as u64 };
+            let _ = syscall::epoll_ctl(epfd, syscall::EPOLL_CTL_MOD, fd, &mut ev);
+        }
+    }
+    to_wake
+}
+
+/// Translate raw `epoll_event.events` bits into the `READABLE`/`WRITABLE`/
+/// `CLOSED` vocabulary the rest of the reactor uses.
+pub fn translate_epoll_events(events: u32) -> i16 {
+    const EPOLLIN: u32 = 0x001;
+    const EPOLLOUT: u32 = 0x004;
+    const EPOLLERR: u32 = 0x008;
+    const EPOLLHUP: u32 = 0x010;
+    let mut r = 0i16;
+    if events & EPOLLIN != 0 {
+        r |= READABLE;
+    }
+    if events & EPOLLOUT != 0 {
+        r |= WRITABLE;
+    }
+    if events & (EPOLLERR | EPOLLHUP) != 0 {
+        r |= CLOSED;
+    }
+    r
+}
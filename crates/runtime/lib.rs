@@ -22,24 +22,37 @@ pub fn log_write(s: &[u8]) {
 }
 
 mod allocator;
+mod blocking;
+pub mod channel;
+pub mod coop;
 mod io_registry;
 mod scheduler;
+mod timer;
 
 // Re-export public API
-pub use io_registry::{close_eventfd, register_fd_waker, unregister_fd};
+pub use blocking::spawn_blocking;
+pub use channel::{channel, Receiver, SendError, Sender};
+/// Cooperative polling budget check - see [`coop`] for the full picture.
+/// Re-exported at the top level since every I/O leaf future calls this.
+pub use coop::poll_proceed;
+pub use io_registry::{close_eventfd, register_fd_waker, unregister_fd, READABLE, WRITABLE};
 pub use scheduler::{
-    create_waker, dump_scheduled, is_handle_scheduled, poll_task_safe, spawn, take_scheduled_task,
-    wake_handle,
+    abort_handle, create_waker, dump_scheduled, init_worker_queues, is_handle_scheduled,
+    poll_task_safe, register_task, set_current_worker_id, spawn, take_scheduled_task, wake_handle,
+    Cancelled, JoinHandle,
 };
+pub use timer::{sleep_ns, timeout, Elapsed, Sleep, Timeout};
 
-/// Ergonomic spawn helper - automatically boxes the future and wakes it
+/// Ergonomic spawn helper - automatically boxes the future and wakes it.
+/// Fire-and-forget: for callers that need the task's output, use `spawn`
+/// instead, which returns a `JoinHandle`.
 #[inline]
 pub fn spawn_task<F>(future: F) -> usize
 where
     F: core::future::Future<Output = ()> + Send + 'static,
 {
     use alloc::boxed::Box;
-    let handle = spawn(Box::new(future));
+    let handle = register_task(Box::new(future));
     wake_handle(handle);
     handle
 }
@@ -67,6 +80,11 @@ fn install_sigchld_handler() {
     );
 }
 
+/// Maximum ready events drained per `epoll_wait` call. Generous relative to
+/// the expected number of simultaneously-ready connections; any overflow is
+/// simply picked up on the next reactor turn.
+const MAX_EPOLL_EVENTS: usize = 256;
+
 pub fn ppoll_and_schedule() {
     // Reap exited child processes
     loop {
@@ -77,100 +95,69 @@ pub fn ppoll_and_schedule() {
         }
     }
 
-    let snapshot: Vec<(i32, i16)> = {
-        let reg = io_registry::IO_REG.lock();
-        reg.iter().map(|e| (e.fd, e.events)).collect()
-    };
-
-    let evt = io_registry::ensure_eventfd();
-    let mut fds: Vec<syscall::PollFd> = Vec::new();
-
-    // Always include eventfd first (for task wake notifications)
-    if evt >= 0 {
-        fds.push(syscall::PollFd {
-            fd: evt,
-            events: 0x0001,
-            revents: 0,
-        });
-    } else {
-        // No eventfd - can't wait for tasks
-        // Sleep briefly to avoid busy-wait
+    let epfd = io_registry::ensure_epoll_fd();
+    if epfd < 0 {
+        // No epoll instance - can't wait for tasks. Sleep briefly to avoid
+        // busy-waiting.
         let _ = syscall::nanosleep_ns(10_000_000); // 10ms
         return;
     }
 
-    for (fd, ev) in snapshot.iter() {
-        fds.push(syscall::PollFd {
-            fd: *fd,
-            events: *ev,
-            revents: 0,
-        });
-    }
+    // Bound the wait by the nearest timer deadline, if any; otherwise block
+    // until an IO event arrives like before.
+    let timeout_ms = match timer::next_deadline_ns() {
+        Some(deadline) => {
+            let now = timer::now_ns();
+            if deadline <= now { 0 } else { ((deadline - now) / 1_000_000) as i64 }
+        }
+        None => -1,
+    };
 
-    // Use infinite timeout ppoll - blocks until events are ready
-    let ret = syscall::ppoll(fds.as_mut_ptr(), fds.len());
+    let mut events = [syscall::EpollEvent { events: 0, data: 0 }; MAX_EPOLL_EVENTS];
+    let n = syscall::epoll_wait(epfd, events.as_mut_ptr(), events.len(), timeout_ms);
 
-    if ret <= 0 {
-        // ppoll error or no events
-        return;
-    }
+    // Fire any timers whose deadline has passed, whether epoll_wait woke us
+    // for IO or simply timed out.
+    timer::fire_expired(timer::now_ns());
 
-    // Drain eventfd
-    if evt >= 0 && fds[0].revents != 0 {
-        let mut buf = [0u8; 8];
-        let _ = syscall::read(evt, &mut buf);
-        io_registry::EVENT_PENDING.store(0, Ordering::Relaxed);
+    if n <= 0 {
+        // epoll_wait error or timeout with no IO events
+        return;
     }
 
-    let start = if evt >= 0 { 1 } else { 0 };
     let mut ready_count = 0;
-    for pf in fds.iter().skip(start) {
-        // Diagnostic per-fd revents
-        if pf.revents != 0 {
-            ready_count += 1;
-            // POLLERR=0x08, POLLHUP=0x10, POLLNVAL=0x20
-            let is_closed = (pf.revents & 0x38) != 0;
-
-            // Log which fd and what events
-            log_write(b"[ppoll] fd=");
-            syscall::write_usize(LOG_FD.load(Ordering::Relaxed), pf.fd as usize);
-            log_write(b" revents=0x");
-            syscall::write_hex(LOG_FD.load(Ordering::Relaxed), pf.revents as usize);
-            log_write(b" closed=");
-            syscall::write_usize(
-                LOG_FD.load(Ordering::Relaxed),
-                if is_closed { 1 } else { 0 },
-            );
-            log_write(b"\n");
-
-            let mut to_wake: Vec<core::task::Waker> = Vec::new();
-            {
-                let mut reg = io_registry::IO_REG.lock();
-                for i in 0..reg.len() {
-                    if reg[i].fd == pf.fd {
-                        // Take wakers (will be re-registered on next poll if needed)
-                        core::mem::swap(&mut to_wake, &mut reg[i].waiters);
-
-                        // Always remove entry - task will re-register if it needs to wait again
-                        if is_closed {
-                            log_write(b"[ppoll] removing closed fd=");
-                            syscall::write_usize(LOG_FD.load(Ordering::Relaxed), pf.fd as usize);
-                            log_write(b"\n");
-                        }
-                        reg.swap_remove(i);
-                        break;
-                    }
-                }
-            }
-            // Wake tasks - they will add new wakers on next poll
-            for w in to_wake {
-                w.wake();
-            }
+    for ev in events.iter().take(n as usize) {
+        if ev.data == io_registry::eventfd_data() {
+            // Drain the eventfd (task-wake channel); nothing else to do.
+            let evt = io_registry::ensure_eventfd();
+            let mut buf = [0u8; 8];
+            let _ = syscall::read(evt, &mut buf);
+            io_registry::EVENT_PENDING.store(0, Ordering::Relaxed);
+            continue;
+        }
+
+        ready_count += 1;
+        let revents = io_registry::translate_epoll_events(ev.events);
+        let is_closed = revents & io_registry::CLOSED != 0;
+
+        log_write(b"[epoll] slot=");
+        syscall::write_usize(LOG_FD.load(Ordering::Relaxed), ev.data as usize);
+        log_write(b" events=0x");
+        syscall::write_hex(LOG_FD.load(Ordering::Relaxed), ev.events as usize);
+        log_write(b" closed=");
+        syscall::write_usize(LOG_FD.load(Ordering::Relaxed), if is_closed { 1 } else { 0 });
+        log_write(b"\n");
+
+        // Only the waker(s) whose direction intersects `revents` fire; a
+        // waiter still parked on the other direction stays registered.
+        let to_wake = io_registry::mark_ready_and_take_wakers(ev.data as usize, revents);
+        for w in to_wake {
+            w.wake();
         }
     }
 
     if ready_count > 0 {
-        log_write(b"[ppoll] ");
+        log_write(b"[epoll] ");
         syscall::write_usize(LOG_FD.load(Ordering::Relaxed), ready_count);
         log_write(b" fds ready\n");
     }
@@ -282,7 +269,12 @@ pub unsafe fn parse_cstring_ip(s: *const u8) -> Option<u32> {
     None
 }
 
-// Entry point assembly - must be naked, no prologue
+// Entry point assembly - must be naked, no prologue. One variant per arch
+// to match the per-arch split already done for the syscall layer
+// (`async_syscall::arch`) - the kernel hands every architecture the same
+// `argc`/`argv`/`envp`/auxv layout at the initial stack pointer, but the
+// registers used to carry them into `main_trampoline` are arch-specific.
+#[cfg(target_arch = "x86_64")]
 core::arch::global_asm!(
     ".section .text._start,\"ax\",@progbits",
     ".globl _start",
@@ -297,21 +289,37 @@ core::arch::global_asm!(
     main_trampoline = sym main_trampoline
 );
 
-// OLD VERSION - remove this
-/*
-#[unsafe(no_mangle)]
-#[unsafe(link_section = ".text._start")]
-pub unsafe extern "C" fn _start() -> ! {
-    core::arch::asm!(
-        "pop rdi",              // argc
-        "mov rsi, rsp",         // argv
-        "and rsp, ~15",         // align
-        "call {main}",
-        main = sym main_trampoline,
-        options(noreturn)
-    )
-}
-*/
+#[cfg(target_arch = "aarch64")]
+core::arch::global_asm!(
+    ".section .text._start,\"ax\",@progbits",
+    ".globl _start",
+    ".type _start, @function",
+    "_start:",
+    "ldr x0, [sp]",         // argc
+    "add x1, sp, #8",       // argv
+    "and sp, sp, #-16",     // align stack
+    "mov x29, xzr",         // clear frame pointer
+    "mov x30, xzr",         // clear link register
+    "bl {main_trampoline}",
+    "brk #0",               // should never return
+    main_trampoline = sym main_trampoline
+);
+
+#[cfg(target_arch = "riscv64")]
+core::arch::global_asm!(
+    ".section .text._start,\"ax\",@progbits",
+    ".globl _start",
+    ".type _start, @function",
+    "_start:",
+    "ld a0, 0(sp)",         // argc
+    "addi a1, sp, 8",       // argv
+    "andi sp, sp, -16",     // align stack
+    "mv fp, zero",          // clear frame pointer
+    "mv ra, zero",          // clear return address
+    "call {main_trampoline}",
+    "unimp",                // should never return
+    main_trampoline = sym main_trampoline
+);
 
 extern "C" fn main_trampoline(argc: isize, argv: *const *const u8) -> ! {
     install_sigchld_handler();
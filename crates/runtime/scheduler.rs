@@ -1,10 +1,14 @@
-//! Task scheduler - Treiber stack for lock-free scheduling
+//! Task scheduler - per-worker local run queues with a Treiber stack kept as
+//! the overflow/injection queue, so a wake from inside a worker's own poll
+//! loop doesn't have to contend with every other worker on one cache line.
 
 use alloc::boxed::Box;
+use alloc::collections::VecDeque;
 use alloc::vec::Vec;
 use core::future::Future;
+use core::pin::Pin;
 use async_syscall as sys;
-use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
 use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
 
 struct Node {
@@ -15,7 +19,95 @@ struct Node {
 static SCHEDULE_HEAD: AtomicPtr<Node> = AtomicPtr::new(core::ptr::null_mut());
 static FREELIST_HEAD: AtomicPtr<Node> = AtomicPtr::new(core::ptr::null_mut());
 static FREELIST_COUNT: AtomicUsize = AtomicUsize::new(0);
-use crate::config::FREELIST_MAX;
+use crate::config::{FREELIST_MAX, LOCAL_QUEUE_CAP};
+
+/// Reserved TLS word (see `async_syscall::tls_word`) holding this worker's
+/// index into `WORKER_QUEUES`, offset by one so 0 means "not a worker
+/// thread" (the TLS page starts zeroed).
+const WORKER_ID_TLS_SLOT: usize = 1;
+
+struct LocalQueue {
+    items: spin::Mutex<VecDeque<usize>>,
+}
+
+impl LocalQueue {
+    fn new() -> Self {
+        Self { items: spin::Mutex::new(VecDeque::new()) }
+    }
+
+    /// Push a handle, returning `false` if the queue is at `LOCAL_QUEUE_CAP`
+    /// so the caller can fall back to the global injection stack.
+    fn push(&self, handle: usize) -> bool {
+        let mut items = self.items.lock();
+        if items.len() >= LOCAL_QUEUE_CAP {
+            return false;
+        }
+        items.push_back(handle);
+        true
+    }
+
+    fn pop(&self) -> Option<usize> {
+        self.items.lock().pop_front()
+    }
+
+    /// Steal the newest half of this queue's handles, leaving the owner's
+    /// oldest (soonest-due) work alone.
+    fn steal_half(&self) -> Vec<usize> {
+        let mut items = self.items.lock();
+        let half = items.len() / 2;
+        if half == 0 {
+            return Vec::new();
+        }
+        let split_at = items.len() - half;
+        items.split_off(split_at).into_iter().collect()
+    }
+}
+
+static WORKER_QUEUES: spin::Mutex<Option<Vec<LocalQueue>>> = spin::Mutex::new(None);
+
+/// Allocate `num_workers` local run queues. Called once at startup before
+/// any worker thread is spawned.
+pub fn init_worker_queues(num_workers: usize) {
+    let mut v = Vec::with_capacity(num_workers);
+    for _ in 0..num_workers {
+        v.push(LocalQueue::new());
+    }
+    *WORKER_QUEUES.lock() = Some(v);
+}
+
+/// Record the calling thread as worker `id` - must be called once on every
+/// worker thread (including the main thread, if it joins in as a worker)
+/// before that thread's first `wake_handle`/`take_scheduled_task` call.
+pub fn set_current_worker_id(id: usize) {
+    sys::set_tls_word(WORKER_ID_TLS_SLOT, id + 1);
+}
+
+fn current_worker_id() -> Option<usize> {
+    // Reading a TLS word is only safe once this thread's TLS base register
+    // is set up (via `CLONE_SETTLS` for spawned workers, or an explicit
+    // `init_thread_tls` + `set_current_worker_id` for the main thread).
+    // `WORKER_QUEUES` isn't populated until `init_worker_queues` runs in
+    // `start_workers`, so treat "not yet initialized" as "not a worker
+    // thread" rather than risk reading an unset segment base - callers like
+    // the initial `spawn_task` before `start_workers` hit this path.
+    if WORKER_QUEUES.lock().is_none() {
+        return None;
+    }
+    let raw = sys::tls_word(WORKER_ID_TLS_SLOT);
+    if raw == 0 { None } else { Some(raw - 1) }
+}
+
+/// Pick a sibling to steal from. Not cryptographic - just enough spread to
+/// avoid every idle worker hammering the same victim - seeded from the
+/// monotonic clock so repeated calls on the same worker don't all agree.
+fn pick_victim(self_id: usize, count: usize) -> usize {
+    let mut seed = sys::clock_gettime_monotonic_ns() ^ ((self_id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    seed ^= seed << 13;
+    seed ^= seed >> 7;
+    seed ^= seed << 17;
+    let victim = (seed as usize) % count;
+    if victim == self_id { (victim + 1) % count } else { victim }
+}
 
 // Per-slot task storage
 use crate::config::MAX_TASK_SLOTS;
@@ -23,6 +115,11 @@ use crate::config::MAX_TASK_SLOTS;
 struct Slot {
     generation: AtomicUsize,
     inner: spin::Mutex<Option<Box<dyn Future<Output = ()> + Send>>>,
+    /// Set by `abort_handle` when it finds `inner` already locked (the task
+    /// is mid-poll, possibly aborting itself) instead of blocking on a lock
+    /// this thread might already hold. `poll_task_safe` checks it right
+    /// after polling and drops the task instead of reinserting it.
+    abort_requested: AtomicBool,
 }
 
 impl Slot {
@@ -30,6 +127,7 @@ impl Slot {
         Self {
             generation: AtomicUsize::new(0),
             inner: spin::Mutex::new(None),
+            abort_requested: AtomicBool::new(false),
         }
     }
 }
@@ -119,7 +217,161 @@ pub fn register_task(task: Box<dyn Future<Output = ()> + Send>) -> usize {
     (slot_idx << 32) | (generation & 0xFFFFFFFF)
 }
 
+/// Shared state between a spawned task's `JoinAdapter` and its `JoinHandle`,
+/// since this is no_std and we'd rather not pull in `Arc`'s refcounting for
+/// what is always exactly two owners. The `JoinHandle` owns freeing it: it
+/// frees the box once it reads a `Ready` result, or never if the caller
+/// drops the handle before the task completes (same leak-on-early-drop
+/// tradeoff the rest of this runtime already makes elsewhere).
+pub(crate) struct JoinState<T> {
+    result: spin::Mutex<Option<T>>,
+    waker: spin::Mutex<Option<Waker>>,
+    /// Set when the task backing this state was dropped (via `abort_handle`)
+    /// before producing a result. `spawn_blocking`'s `JoinState` never sets
+    /// this - a running closure on a dedicated OS thread can't be aborted.
+    cancelled: AtomicBool,
+}
+
+/// A spawned task was aborted before its `JoinHandle` observed a result.
+pub struct Cancelled;
+
+/// Allocate a standalone `JoinState`, for callers that fulfill it themselves
+/// instead of going through a polled `JoinAdapter` - e.g. `spawn_blocking`,
+/// whose job runs as a plain closure on a dedicated OS thread.
+pub(crate) fn new_join_state<T>() -> *const JoinState<T> {
+    Box::into_raw(Box::new(JoinState {
+        result: spin::Mutex::new(None),
+        waker: spin::Mutex::new(None),
+        cancelled: AtomicBool::new(false),
+    }))
+}
+
+/// Wrap a raw `JoinState` in the `JoinHandle` callers actually await.
+pub(crate) fn join_handle_from_raw<T>(state: *const JoinState<T>) -> JoinHandle<T> {
+    JoinHandle { state }
+}
+
+/// Store `value` as the result and wake whoever is awaiting the matching
+/// `JoinHandle`, if it's already parked one.
+pub(crate) fn fulfill_join_state<T>(state: *const JoinState<T>, value: T) {
+    let state = unsafe { &*state };
+    *state.result.lock() = Some(value);
+    if let Some(w) = state.waker.lock().take() {
+        w.wake();
+    }
+}
+
+/// Wraps a spawned future so its output lands in a `JoinState` instead of
+/// being discarded - the existing `Box<dyn Future<Output = ()>>` task slots
+/// are untyped, so this is what actually runs inside them.
+struct JoinAdapter<F: Future> {
+    inner: F,
+    state: *const JoinState<F::Output>,
+}
+
+// The adapter is moved into a task slot and polled by worker threads; the
+// wrapped future and its output are both required to be `Send` by `spawn`.
+unsafe impl<F: Future + Send> Send for JoinAdapter<F> {}
+
+impl<F: Future> Future for JoinAdapter<F> {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = unsafe { self.get_unchecked_mut() };
+        let inner = unsafe { Pin::new_unchecked(&mut this.inner) };
+        match inner.poll(cx) {
+            Poll::Ready(value) => {
+                let state = unsafe { &*this.state };
+                *state.result.lock() = Some(value);
+                if let Some(w) = state.waker.lock().take() {
+                    w.wake();
+                }
+                Poll::Ready(())
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<F: Future> Drop for JoinAdapter<F> {
+    fn drop(&mut self) {
+        // Only an abort drops an adapter before it has produced a result -
+        // a normal completion already wrote `state.result` above and won't
+        // reach this with it still empty. Mark the state cancelled and wake
+        // whoever's awaiting the JoinHandle; it stays alive (the handle
+        // frees it) since this side is the one going away.
+        let state = unsafe { &*self.state };
+        if state.result.lock().is_none() {
+            state.cancelled.store(true, Ordering::Relaxed);
+            if let Some(w) = state.waker.lock().take() {
+                w.wake();
+            }
+        }
+    }
+}
+
+/// Handle to a task spawned via [`spawn`], awaitable for its output. If the
+/// task is aborted (see [`abort_handle`]) before completing, resolves to
+/// `Err(Cancelled)` instead.
+pub struct JoinHandle<T> {
+    state: *const JoinState<T>,
+}
+
+unsafe impl<T: Send> Send for JoinHandle<T> {}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, Cancelled>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let state = unsafe { &*self.state };
+        if let Some(value) = state.result.lock().take() {
+            // Safety: the JoinAdapter never touches `state` again once it
+            // has written the result, so the handle is the sole owner here.
+            unsafe {
+                drop(Box::from_raw(self.state as *mut JoinState<T>));
+            }
+            return Poll::Ready(Ok(value));
+        }
+        if state.cancelled.load(Ordering::Relaxed) {
+            unsafe {
+                drop(Box::from_raw(self.state as *mut JoinState<T>));
+            }
+            return Poll::Ready(Err(Cancelled));
+        }
+        *state.waker.lock() = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Spawn `fut` and return a [`JoinHandle`] that resolves to its output once
+/// the task completes. Fire-and-forget tasks should keep using
+/// `register_task`/`spawn_task` directly - this is for callers that need
+/// the result.
+pub fn spawn<F>(fut: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let state = new_join_state::<F::Output>();
+    let adapter: Box<dyn Future<Output = ()> + Send> = Box::new(JoinAdapter { inner: fut, state });
+    let handle = register_task(adapter);
+    wake_handle(handle);
+    JoinHandle { state }
+}
+
+/// Schedule `handle`. If called on a worker thread (i.e. from within a
+/// poll), this pushes onto that worker's own local queue so it can pick the
+/// task back up without touching any shared state. Otherwise - or if the
+/// local queue is full - it falls back to the global injection stack and
+/// wakes a (possibly sleeping) worker via the eventfd.
 pub fn wake_handle(handle: usize) {
+    if let Some(id) = current_worker_id() {
+        let guard = WORKER_QUEUES.lock();
+        if let Some(queues) = guard.as_ref() {
+            if id < queues.len() && queues[id].push(handle) {
+                return;
+            }
+        }
+    }
+
     let node = alloc_node(handle);
     loop {
         let head = SCHEDULE_HEAD.load(Ordering::Acquire);
@@ -135,7 +387,7 @@ pub fn wake_handle(handle: usize) {
     }
 }
 
-pub fn take_scheduled_task() -> Option<usize> {
+fn take_from_global() -> Option<usize> {
     loop {
         let head = SCHEDULE_HEAD.load(Ordering::Acquire);
         if head.is_null() {
@@ -147,13 +399,60 @@ pub fn take_scheduled_task() -> Option<usize> {
             .is_ok()
         {
             let handle = unsafe { (*head).handle };
-            // taken handle
             free_node(head);
             return Some(handle);
         }
     }
 }
 
+/// Drain this worker's own local queue first, then try to steal half of a
+/// sibling's queue, and only fall back to the shared global stack (the
+/// overflow/injection queue) once both are empty.
+pub fn take_scheduled_task() -> Option<usize> {
+    if let Some(id) = current_worker_id() {
+        let guard = WORKER_QUEUES.lock();
+        if let Some(queues) = guard.as_ref().filter(|q| id < q.len()) {
+            if let Some(handle) = queues[id].pop() {
+                drop(guard);
+                crate::coop::reset_budget();
+                return Some(handle);
+            }
+
+            let count = queues.len();
+            if count > 1 {
+                let victim = pick_victim(id, count);
+                let mut stolen = queues[victim].steal_half();
+                if !stolen.is_empty() {
+                    // Keep one for this worker, hand the rest to its own
+                    // queue for later turns.
+                    let handle = stolen.remove(0);
+                    // Queue just got drained, so this should always have
+                    // room; if it somehow doesn't (very unlikely right
+                    // after popping it above), remember the leftovers and
+                    // push them onto the global stack once the lock is free
+                    // rather than dropping them.
+                    let mut overflow = Vec::new();
+                    for h in stolen {
+                        if !queues[id].push(h) {
+                            overflow.push(h);
+                        }
+                    }
+                    drop(guard);
+                    for h in overflow {
+                        wake_handle(h);
+                    }
+                    crate::coop::reset_budget();
+                    return Some(handle);
+                }
+            }
+        }
+    }
+
+    let handle = take_from_global()?;
+    crate::coop::reset_budget();
+    Some(handle)
+}
+
 /// Check whether a handle is currently present in the scheduled Treiber stack.
 pub fn is_handle_scheduled(target: usize) -> bool {
     let mut cur = SCHEDULE_HEAD.load(Ordering::Acquire);
@@ -208,18 +507,64 @@ pub fn poll_task_safe(handle: usize, cx: &mut Context<'_>) -> Poll<()> {
     if let Some(task) = guard.as_mut() {
         let pin = unsafe { core::pin::Pin::new_unchecked(task.as_mut()) };
         let result = pin.poll(cx);
-        if matches!(result, Poll::Ready(_)) {
+        // An abort that arrived while this poll was in flight couldn't take
+        // the lock to drop the task itself, so it just flagged the slot -
+        // honor that now regardless of what the poll returned.
+        let aborted = slots[slot_idx].abort_requested.swap(false, Ordering::Relaxed);
+        if aborted || matches!(result, Poll::Ready(_)) {
             *guard = None;
             drop(guard);
             FREE_SLOTS.lock().push(slot_idx);
+            return Poll::Ready(());
         }
-        // poll result handled by caller
         result
     } else {
         Poll::Ready(())
     }
 }
 
+/// Abort the task behind `handle`, dropping its boxed future (running its
+/// destructors - closing fds, etc.) and recycling the slot. If it was
+/// spawned via [`spawn`] and a [`JoinHandle`] is waiting on it, that handle
+/// resolves to `Err(Cancelled)` instead of its usual output (via
+/// `JoinAdapter`'s `Drop` impl).
+///
+/// A stale `handle` (already completed, or recycled and reused) is silently
+/// ignored rather than touching the wrong task.
+pub fn abort_handle(handle: usize) {
+    let slot_idx = (handle >> 32) & 0x3FF;
+    let generation = handle & 0xFFFFFFFF;
+
+    let slots_guard = SLOTS.lock();
+    let slots = match slots_guard.as_ref() {
+        Some(s) => s,
+        None => return,
+    };
+    if slot_idx >= slots.len() {
+        return;
+    }
+    let cur_generation = slots[slot_idx].generation.load(Ordering::Relaxed);
+    if cur_generation != generation && cur_generation != generation + 1 {
+        return;
+    }
+
+    match slots[slot_idx].inner.try_lock() {
+        Some(mut guard) => {
+            if guard.take().is_some() {
+                drop(guard);
+                FREE_SLOTS.lock().push(slot_idx);
+            }
+        }
+        None => {
+            // Currently being polled - possibly this very task aborting
+            // itself, on this same thread, which would deadlock on `.lock()`
+            // here. Leave the flag for `poll_task_safe` to act on once the
+            // in-flight poll returns instead.
+            slots[slot_idx].abort_requested.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
 unsafe fn clone_waker(data: *const ()) -> RawWaker {
     RawWaker::new(data, &VTABLE)
 }
@@ -0,0 +1,418 @@
+//! Per-architecture syscall primitives and syscall number tables.
+//!
+//! Every arch module below exposes the same `syscall0..syscall6` signatures
+//! (args and return value always passed as `u64`/`i64`, the kernel ABI for
+//! all three targets) using that architecture's syscall instruction and
+//! argument registers, plus a `nr` module of syscall numbers. The rest of
+//! this crate calls through `arch::syscallN(arch::nr::WHATEVER, ...)` and
+//! never hardcodes a number or register name itself.
+//!
+//! aarch64 and riscv64 share the same syscall numbers (both use the kernel's
+//! generic `asm-generic/unistd.h` table), so `generic64_nr` is defined once
+//! and reused by both.
+
+#[cfg(target_arch = "x86_64")]
+mod x86_64_impl {
+    #[allow(dead_code)]
+    pub unsafe fn syscall0(n: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "syscall",
+                in("rax") n,
+                lateout("rax") ret,
+                lateout("rcx") _,
+                lateout("r11") _,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall1(n: u64, a1: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "syscall",
+                in("rax") n,
+                in("rdi") a1,
+                lateout("rax") ret,
+                lateout("rcx") _,
+                lateout("r11") _,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall2(n: u64, a1: u64, a2: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "syscall",
+                in("rax") n,
+                in("rdi") a1,
+                in("rsi") a2,
+                lateout("rax") ret,
+                lateout("rcx") _,
+                lateout("r11") _,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall3(n: u64, a1: u64, a2: u64, a3: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "syscall",
+                in("rax") n,
+                in("rdi") a1,
+                in("rsi") a2,
+                in("rdx") a3,
+                lateout("rax") ret,
+                lateout("rcx") _,
+                lateout("r11") _,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall4(n: u64, a1: u64, a2: u64, a3: u64, a4: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "syscall",
+                in("rax") n,
+                in("rdi") a1,
+                in("rsi") a2,
+                in("rdx") a3,
+                in("r10") a4,
+                lateout("rax") ret,
+                lateout("rcx") _,
+                lateout("r11") _,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall5(n: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "syscall",
+                in("rax") n,
+                in("rdi") a1,
+                in("rsi") a2,
+                in("rdx") a3,
+                in("r10") a4,
+                in("r8") a5,
+                lateout("rax") ret,
+                lateout("rcx") _,
+                lateout("r11") _,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall6(n: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64, a6: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "syscall",
+                in("rax") n,
+                in("rdi") a1,
+                in("rsi") a2,
+                in("rdx") a3,
+                in("r10") a4,
+                in("r8") a5,
+                in("r9") a6,
+                lateout("rax") ret,
+                lateout("rcx") _,
+                lateout("r11") _,
+            );
+        }
+        ret
+    }
+
+    /// x86_64 syscall numbers. x86_64 is the one target of these three with
+    /// a plain `open` (no dirfd) and a plain `epoll_wait`, and the only one
+    /// with `arch_prctl` - the others set their TLS base register directly.
+    pub mod nr {
+        pub const READ: u64 = 0;
+        pub const WRITE: u64 = 1;
+        pub const OPEN: u64 = 2;
+        pub const CLOSE: u64 = 3;
+        pub const MMAP: u64 = 9;
+        pub const IOCTL: u64 = 16;
+        pub const READV: u64 = 19;
+        pub const WRITEV: u64 = 20;
+        pub const RT_SIGACTION: u64 = 13;
+        pub const SOCKET: u64 = 41;
+        pub const CONNECT: u64 = 42;
+        pub const SENDTO: u64 = 44;
+        pub const RECVFROM: u64 = 45;
+        pub const BIND: u64 = 49;
+        pub const LISTEN: u64 = 50;
+        pub const GETSOCKNAME: u64 = 51;
+        pub const GETPEERNAME: u64 = 52;
+        pub const SETSOCKOPT: u64 = 54;
+        pub const CLONE: u64 = 56;
+        pub const EXIT: u64 = 60;
+        pub const WAIT4: u64 = 61;
+        pub const NANOSLEEP: u64 = 35;
+        pub const FCNTL: u64 = 72;
+        pub const PPOLL: u64 = 271;
+        pub const ACCEPT4: u64 = 288;
+        pub const EVENTFD2: u64 = 290;
+        pub const EPOLL_CREATE1: u64 = 291;
+        pub const EPOLL_CTL: u64 = 233;
+        pub const EPOLL_WAIT: u64 = 232;
+        pub const CLOCK_GETTIME: u64 = 228;
+        pub const ARCH_PRCTL: u64 = 158;
+        pub const PREADV: u64 = 295;
+        pub const PWRITEV: u64 = 296;
+        pub const SENDFILE: u64 = 40;
+        pub const PIPE2: u64 = 293;
+        pub const SPLICE: u64 = 275;
+        pub const SENDMSG: u64 = 46;
+        pub const RECVMSG: u64 = 47;
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+pub use x86_64_impl::{nr, syscall0, syscall1, syscall2, syscall3, syscall4, syscall5, syscall6};
+
+/// Syscall numbers shared by aarch64 and riscv64 - both expose the kernel's
+/// generic `asm-generic/unistd.h` table rather than an arch-private one.
+/// Neither has a plain `open` or `epoll_wait`: callers go through `openat`
+/// with `AT_FDCWD` and `epoll_pwait` with a null sigmask instead.
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+mod generic64_nr {
+    pub const AT_FDCWD: i32 = -100;
+    pub const IOCTL: u64 = 29;
+    pub const EVENTFD2: u64 = 19;
+    pub const EPOLL_CREATE1: u64 = 20;
+    pub const EPOLL_CTL: u64 = 21;
+    pub const EPOLL_PWAIT: u64 = 22;
+    pub const OPENAT: u64 = 56;
+    pub const CLOSE: u64 = 57;
+    pub const READ: u64 = 63;
+    pub const WRITE: u64 = 64;
+    pub const READV: u64 = 65;
+    pub const WRITEV: u64 = 66;
+    pub const FCNTL: u64 = 25;
+    pub const PPOLL: u64 = 73;
+    pub const NANOSLEEP: u64 = 101;
+    pub const CLOCK_GETTIME: u64 = 113;
+    pub const RT_SIGACTION: u64 = 134;
+    pub const SOCKET: u64 = 198;
+    pub const BIND: u64 = 200;
+    pub const LISTEN: u64 = 201;
+    pub const CONNECT: u64 = 203;
+    pub const GETSOCKNAME: u64 = 204;
+    pub const GETPEERNAME: u64 = 205;
+    pub const SENDTO: u64 = 206;
+    pub const RECVFROM: u64 = 207;
+    pub const SETSOCKOPT: u64 = 208;
+    pub const ACCEPT4: u64 = 242;
+    pub const MMAP: u64 = 222;
+    pub const CLONE: u64 = 220;
+    pub const EXIT: u64 = 93;
+    pub const WAIT4: u64 = 260;
+    pub const PREADV: u64 = 69;
+    pub const PWRITEV: u64 = 70;
+    pub const SENDFILE: u64 = 71;
+    pub const PIPE2: u64 = 59;
+    pub const SPLICE: u64 = 76;
+    pub const SENDMSG: u64 = 211;
+    pub const RECVMSG: u64 = 212;
+}
+
+#[cfg(target_arch = "aarch64")]
+mod aarch64_impl {
+    #[allow(dead_code)]
+    pub unsafe fn syscall0(n: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!("svc #0", in("x8") n, lateout("x0") ret);
+        }
+        ret
+    }
+
+    pub unsafe fn syscall1(n: u64, a1: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!("svc #0", in("x8") n, inlateout("x0") a1 => ret);
+        }
+        ret
+    }
+
+    pub unsafe fn syscall2(n: u64, a1: u64, a2: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!("svc #0", in("x8") n, inlateout("x0") a1 => ret, in("x1") a2);
+        }
+        ret
+    }
+
+    pub unsafe fn syscall3(n: u64, a1: u64, a2: u64, a3: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "svc #0",
+                in("x8") n,
+                inlateout("x0") a1 => ret,
+                in("x1") a2,
+                in("x2") a3,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall4(n: u64, a1: u64, a2: u64, a3: u64, a4: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "svc #0",
+                in("x8") n,
+                inlateout("x0") a1 => ret,
+                in("x1") a2,
+                in("x2") a3,
+                in("x3") a4,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall5(n: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "svc #0",
+                in("x8") n,
+                inlateout("x0") a1 => ret,
+                in("x1") a2,
+                in("x2") a3,
+                in("x3") a4,
+                in("x4") a5,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall6(n: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64, a6: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "svc #0",
+                in("x8") n,
+                inlateout("x0") a1 => ret,
+                in("x1") a2,
+                in("x2") a3,
+                in("x3") a4,
+                in("x4") a5,
+                in("x5") a6,
+            );
+        }
+        ret
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+pub use aarch64_impl::{syscall0, syscall1, syscall2, syscall3, syscall4, syscall5, syscall6};
+#[cfg(target_arch = "aarch64")]
+pub use generic64_nr as nr;
+
+#[cfg(target_arch = "riscv64")]
+mod riscv64_impl {
+    #[allow(dead_code)]
+    pub unsafe fn syscall0(n: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!("ecall", in("a7") n, lateout("a0") ret);
+        }
+        ret
+    }
+
+    pub unsafe fn syscall1(n: u64, a1: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!("ecall", in("a7") n, inlateout("a0") a1 => ret);
+        }
+        ret
+    }
+
+    pub unsafe fn syscall2(n: u64, a1: u64, a2: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!("ecall", in("a7") n, inlateout("a0") a1 => ret, in("a1") a2);
+        }
+        ret
+    }
+
+    pub unsafe fn syscall3(n: u64, a1: u64, a2: u64, a3: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "ecall",
+                in("a7") n,
+                inlateout("a0") a1 => ret,
+                in("a1") a2,
+                in("a2") a3,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall4(n: u64, a1: u64, a2: u64, a3: u64, a4: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "ecall",
+                in("a7") n,
+                inlateout("a0") a1 => ret,
+                in("a1") a2,
+                in("a2") a3,
+                in("a3") a4,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall5(n: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "ecall",
+                in("a7") n,
+                inlateout("a0") a1 => ret,
+                in("a1") a2,
+                in("a2") a3,
+                in("a3") a4,
+                in("a4") a5,
+            );
+        }
+        ret
+    }
+
+    pub unsafe fn syscall6(n: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64, a6: u64) -> i64 {
+        let ret: i64;
+        unsafe {
+            core::arch::asm!(
+                "ecall",
+                in("a7") n,
+                inlateout("a0") a1 => ret,
+                in("a1") a2,
+                in("a2") a3,
+                in("a3") a4,
+                in("a4") a5,
+                in("a5") a6,
+            );
+        }
+        ret
+    }
+}
+
+#[cfg(target_arch = "riscv64")]
+pub use generic64_nr as nr;
+#[cfg(target_arch = "riscv64")]
+pub use riscv64_impl::{syscall0, syscall1, syscall2, syscall3, syscall4, syscall5, syscall6};
@@ -2,124 +2,64 @@
 
 #![no_std]
 
-// Core syscalls
-unsafe fn syscall1(n: u64, a1: u64) -> i64 {
-    let ret: i64;
-    unsafe {
-        core::arch::asm!(
-            "syscall",
-            in("rax") n,
-            in("rdi") a1,
-            lateout("rax") ret,
-            lateout("rcx") _,
-            lateout("r11") _,
-        );
+extern crate alloc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+mod arch;
+use arch::{nr, syscall1, syscall2, syscall3, syscall4, syscall5, syscall6};
+
+/// A kernel error number. Raw syscalls report failure by returning `-errno`
+/// in place of their usual non-negative result, so there's no separate
+/// errno variable to read the way libc exposes one; [`ret`] and friends
+/// pull it back out of that return value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Errno(pub i32);
+
+impl Errno {
+    pub const EINTR: Errno = Errno(4);
+    pub const EAGAIN: Errno = Errno(11);
+    pub const EMFILE: Errno = Errno(24);
+    pub const ECONNRESET: Errno = Errno(104);
+    pub const EINPROGRESS: Errno = Errno(115);
+}
+
+/// Interpret a raw syscall return value. The kernel encodes an error as a
+/// small negative number (`-4095..=-1`, i.e. `-errno`) rather than a
+/// separate out-of-band signal, so anything in that range becomes `Err`,
+/// everything else (including `0`) stays `Ok`.
+fn ret(r: isize) -> Result<isize, Errno> {
+    if (-4095..=-1).contains(&r) {
+        Err(Errno(-r as i32))
+    } else {
+        Ok(r)
     }
-    ret
 }
 
-unsafe fn syscall2(n: u64, a1: u64, a2: u64) -> i64 {
-    let ret: i64;
-    unsafe {
-        core::arch::asm!(
-            "syscall",
-            in("rax") n,
-            in("rdi") a1,
-            in("rsi") a2,
-            lateout("rax") ret,
-            lateout("rcx") _,
-            lateout("r11") _,
-        );
-    }
-    ret
+fn ret_usize(r: isize) -> Result<usize, Errno> {
+    ret(r).map(|v| v as usize)
 }
 
-unsafe fn syscall3(n: u64, a1: u64, a2: u64, a3: u64) -> i64 {
-    let ret: i64;
-    unsafe {
-        core::arch::asm!(
-            "syscall",
-            in("rax") n,
-            in("rdi") a1,
-            in("rsi") a2,
-            in("rdx") a3,
-            lateout("rax") ret,
-            lateout("rcx") _,
-            lateout("r11") _,
-        );
-    }
-    ret
-}
-
-unsafe fn syscall4(n: u64, a1: u64, a2: u64, a3: u64, a4: u64) -> i64 {
-    let ret: i64;
-    unsafe {
-        core::arch::asm!(
-            "syscall",
-            in("rax") n,
-            in("rdi") a1,
-            in("rsi") a2,
-            in("rdx") a3,
-            in("r10") a4,
-            lateout("rax") ret,
-            lateout("rcx") _,
-            lateout("r11") _,
-        );
-    }
-    ret
-}
-
-unsafe fn syscall5(n: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64) -> i64 {
-    let ret: i64;
-    unsafe {
-        core::arch::asm!(
-            "syscall",
-            in("rax") n,
-            in("rdi") a1,
-            in("rsi") a2,
-            in("rdx") a3,
-            in("r10") a4,
-            in("r8") a5,
-            lateout("rax") ret,
-            lateout("rcx") _,
-            lateout("r11") _,
-        );
-    }
-    ret
-}
-
-unsafe fn syscall6(n: u64, a1: u64, a2: u64, a3: u64, a4: u64, a5: u64, a6: u64) -> i64 {
-    let ret: i64;
-    unsafe {
-        core::arch::asm!(
-            "syscall",
-            in("rax") n,
-            in("rdi") a1,
-            in("rsi") a2,
-            in("rdx") a3,
-            in("r10") a4,
-            in("r8") a5,
-            in("r9") a6,
-            lateout("rax") ret,
-            lateout("rcx") _,
-            lateout("r11") _,
-        );
-    }
-    ret
+fn ret_i32(r: isize) -> Result<i32, Errno> {
+    ret(r).map(|v| v as i32)
 }
 
 // Public API - only used syscalls
-pub fn write(fd: i32, buf: &[u8]) -> isize {
-    unsafe { syscall3(1, fd as u64, buf.as_ptr() as u64, buf.len() as u64) as isize }
+pub fn write(fd: i32, buf: &[u8]) -> Result<usize, Errno> {
+    ret_usize(unsafe {
+        syscall3(nr::WRITE, fd as u64, buf.as_ptr() as u64, buf.len() as u64) as isize
+    })
 }
 
-pub fn read(fd: i32, buf: &mut [u8]) -> isize {
-    unsafe { syscall3(0, fd as u64, buf.as_mut_ptr() as u64, buf.len() as u64) as isize }
+pub fn read(fd: i32, buf: &mut [u8]) -> Result<usize, Errno> {
+    ret_usize(unsafe {
+        syscall3(nr::READ, fd as u64, buf.as_mut_ptr() as u64, buf.len() as u64) as isize
+    })
 }
 
 pub fn exit(code: i32) -> ! {
     unsafe {
-        syscall1(60, code as u64);
+        syscall1(nr::EXIT, code as u64);
     }
     loop {
         core::hint::spin_loop();
@@ -127,7 +67,17 @@ pub fn exit(code: i32) -> ! {
 }
 
 pub fn mmap(addr: usize, len: usize, prot: i32, flags: i32) -> *mut u8 {
-    let ret = unsafe { syscall6(9, addr as u64, len as u64, prot as u64, flags as u64, !0, 0) };
+    let ret = unsafe {
+        syscall6(
+            nr::MMAP,
+            addr as u64,
+            len as u64,
+            prot as u64,
+            flags as u64,
+            !0,
+            0,
+        )
+    };
     if ret < 0 {
         core::ptr::null_mut()
     } else {
@@ -136,15 +86,44 @@ pub fn mmap(addr: usize, len: usize, prot: i32, flags: i32) -> *mut u8 {
 }
 
 pub fn eventfd(initval: u32, flags: i32) -> i32 {
-    let ret = unsafe { syscall2(290, initval as u64, flags as u64) };
+    let ret = unsafe { syscall2(nr::EVENTFD2, initval as u64, flags as u64) };
     if ret < 0 { -1 } else { ret as i32 }
 }
 
 pub fn close(fd: i32) -> i32 {
-    let ret = unsafe { syscall1(3, fd as u64) };
+    let ret = unsafe { syscall1(nr::CLOSE, fd as u64) };
+    if ret < 0 { -1 } else { ret as i32 }
+}
+
+pub const O_RDWR: i32 = 0x2;
+pub const O_NOCTTY: i32 = 0x100;
+
+/// x86_64 has a plain `open`; aarch64/riscv64 only expose `openat`, so the
+/// same call becomes `openat(AT_FDCWD, path, flags, mode)` there.
+#[cfg(target_arch = "x86_64")]
+pub fn open(path: *const u8, flags: i32, mode: i32) -> i32 {
+    let ret = unsafe { syscall3(nr::OPEN, path as u64, flags as u64, mode as u64) };
+    if ret < 0 { -1 } else { ret as i32 }
+}
+
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+pub fn open(path: *const u8, flags: i32, mode: i32) -> i32 {
+    let ret = unsafe {
+        syscall4(
+            nr::OPENAT,
+            nr::AT_FDCWD as u64,
+            path as u64,
+            flags as u64,
+            mode as u64,
+        )
+    };
     if ret < 0 { -1 } else { ret as i32 }
 }
 
+pub fn ioctl(fd: i32, request: u64, arg: u64) -> isize {
+    unsafe { syscall3(nr::IOCTL, fd as u64, request, arg) as isize }
+}
+
 // Utility: Format usize to decimal ASCII
 #[inline]
 pub fn format_usize(n: usize) -> ([u8; 20], usize) {
@@ -194,17 +173,90 @@ pub struct PollFd {
 
 pub fn ppoll(fds: *mut PollFd, nfds: usize) -> isize {
     // ppoll with infinite timeout (NULL timespec pointer)
-    unsafe { syscall5(271, fds as u64, nfds as u64, 0, 0, 0) as isize }
+    unsafe { syscall5(nr::PPOLL, fds as u64, nfds as u64, 0, 0, 0) as isize }
 }
 
 pub fn ppoll_timeout(fds: *mut PollFd, nfds: usize, timeout_ms: i64) -> isize {
     // ppoll with timeout: struct timespec { tv_sec, tv_nsec }
     let ts = [timeout_ms / 1000, (timeout_ms % 1000) * 1_000_000];
-    unsafe { syscall5(271, fds as u64, nfds as u64, ts.as_ptr() as u64, 0, 0) as isize }
+    unsafe { syscall5(nr::PPOLL, fds as u64, nfds as u64, ts.as_ptr() as u64, 0, 0) as isize }
+}
+
+/// `struct epoll_event` - packed on x86_64 per the kernel's ABI there, where
+/// `data` would otherwise be padded out to an 8-byte boundary after
+/// `events`; other targets use the kernel's natural (unpacked) layout.
+#[cfg_attr(target_arch = "x86_64", repr(C, packed))]
+#[cfg_attr(not(target_arch = "x86_64"), repr(C))]
+#[derive(Clone, Copy)]
+pub struct EpollEvent {
+    pub events: u32,
+    pub data: u64,
+}
+
+pub const EPOLL_CTL_ADD: i32 = 1;
+pub const EPOLL_CTL_DEL: i32 = 2;
+#[allow(dead_code)]
+pub const EPOLL_CTL_MOD: i32 = 3;
+
+pub fn epoll_create1(flags: i32) -> i32 {
+    unsafe { syscall1(nr::EPOLL_CREATE1, flags as u64) as i32 }
+}
+
+pub fn epoll_ctl(epfd: i32, op: i32, fd: i32, event: *mut EpollEvent) -> isize {
+    unsafe { syscall4(nr::EPOLL_CTL, epfd as u64, op as u64, fd as u64, event as u64) as isize }
+}
+
+/// x86_64 has a plain `epoll_wait`; aarch64/riscv64 only expose
+/// `epoll_pwait`, so the same call becomes `epoll_pwait(..., sigmask: NULL,
+/// sigsetsize: 0)` there.
+#[cfg(target_arch = "x86_64")]
+pub fn epoll_wait(epfd: i32, events: *mut EpollEvent, maxevents: usize, timeout_ms: i64) -> isize {
+    unsafe {
+        syscall4(
+            nr::EPOLL_WAIT,
+            epfd as u64,
+            events as u64,
+            maxevents as u64,
+            timeout_ms as u64,
+        ) as isize
+    }
+}
+
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+pub fn epoll_wait(epfd: i32, events: *mut EpollEvent, maxevents: usize, timeout_ms: i64) -> isize {
+    unsafe {
+        syscall6(
+            nr::EPOLL_PWAIT,
+            epfd as u64,
+            events as u64,
+            maxevents as u64,
+            timeout_ms as u64,
+            0,
+            0,
+        ) as isize
+    }
+}
+
+const CLOCK_MONOTONIC: u64 = 1;
+
+/// `struct timespec` as used by `clock_gettime`.
+#[repr(C)]
+struct Timespec {
+    tv_sec: i64,
+    tv_nsec: i64,
+}
+
+/// Current `CLOCK_MONOTONIC` time in nanoseconds, for timer deadlines.
+pub fn clock_gettime_monotonic_ns() -> u64 {
+    let mut ts = Timespec { tv_sec: 0, tv_nsec: 0 };
+    unsafe {
+        syscall2(nr::CLOCK_GETTIME, CLOCK_MONOTONIC, &mut ts as *mut Timespec as u64);
+    }
+    (ts.tv_sec as u64) * 1_000_000_000 + ts.tv_nsec as u64
 }
 
 pub fn fcntl(fd: i32, cmd: i32, arg: usize) -> isize {
-    unsafe { syscall3(72, fd as u64, cmd as u64, arg as u64) as isize }
+    unsafe { syscall3(nr::FCNTL, fd as u64, cmd as u64, arg as u64) as isize }
 }
 
 pub const F_SETFL: i32 = 4;
@@ -212,14 +264,14 @@ pub const O_NONBLOCK: usize = 0x800;
 
 // Socket syscalls
 pub fn socket(domain: i32, type_: i32, protocol: i32) -> i32 {
-    let ret = unsafe { syscall3(41, domain as u64, type_ as u64, protocol as u64) };
+    let ret = unsafe { syscall3(nr::SOCKET, domain as u64, type_ as u64, protocol as u64) };
     if ret < 0 { -1 } else { ret as i32 }
 }
 
 pub fn setsockopt(fd: i32, level: i32, optname: i32, optval: *const u8, optlen: usize) -> i32 {
     let ret = unsafe {
         syscall5(
-            54,
+            nr::SETSOCKOPT,
             fd as u64,
             level as u64,
             optname as u64,
@@ -235,19 +287,21 @@ pub const SO_REUSEADDR: i32 = 2;
 pub const SO_REUSEPORT: i32 = 15;
 
 pub fn bind(fd: i32, addr: *const u8, addrlen: usize) -> isize {
-    unsafe { syscall3(49, fd as u64, addr as u64, addrlen as u64) as isize }
+    unsafe { syscall3(nr::BIND, fd as u64, addr as u64, addrlen as u64) as isize }
 }
 
 pub fn listen(fd: i32, backlog: i32) -> isize {
-    unsafe { syscall2(50, fd as u64, backlog as u64) as isize }
+    unsafe { syscall2(nr::LISTEN, fd as u64, backlog as u64) as isize }
 }
 
-pub fn accept4(fd: i32, addr: *mut u8, addrlen: *mut usize, flags: i32) -> isize {
-    unsafe { syscall4(288, fd as u64, addr as u64, addrlen as u64, flags as u64) as isize }
+pub fn accept4(fd: i32, addr: *mut u8, addrlen: *mut usize, flags: i32) -> Result<i32, Errno> {
+    ret_i32(unsafe {
+        syscall4(nr::ACCEPT4, fd as u64, addr as u64, addrlen as u64, flags as u64) as isize
+    })
 }
 
-pub fn connect(fd: i32, addr: *const u8, addrlen: usize) -> isize {
-    unsafe { syscall3(42, fd as u64, addr as u64, addrlen as u64) as isize }
+pub fn connect(fd: i32, addr: *const u8, addrlen: usize) -> Result<i32, Errno> {
+    ret_i32(unsafe { syscall3(nr::CONNECT, fd as u64, addr as u64, addrlen as u64) as isize })
 }
 
 pub fn sendto(
@@ -257,10 +311,10 @@ pub fn sendto(
     flags: i32,
     dest: *const u8,
     addrlen: usize,
-) -> isize {
-    unsafe {
+) -> Result<usize, Errno> {
+    ret_usize(unsafe {
         syscall6(
-            44,
+            nr::SENDTO,
             fd as u64,
             buf as u64,
             len as u64,
@@ -268,7 +322,7 @@ pub fn sendto(
             dest as u64,
             addrlen as u64,
         ) as isize
-    }
+    })
 }
 
 pub fn recvfrom(
@@ -278,10 +332,10 @@ pub fn recvfrom(
     flags: i32,
     src: *mut u8,
     addrlen: *mut usize,
-) -> isize {
-    unsafe {
+) -> Result<usize, Errno> {
+    ret_usize(unsafe {
         syscall6(
-            45,
+            nr::RECVFROM,
             fd as u64,
             buf as u64,
             len as u64,
@@ -289,15 +343,292 @@ pub fn recvfrom(
             src as u64,
             addrlen as u64,
         ) as isize
-    }
+    })
 }
 
 pub fn getsockname(fd: i32, addr: *mut u8, addrlen: *mut usize) -> isize {
-    unsafe { syscall3(51, fd as u64, addr as u64, addrlen as u64) as isize }
+    unsafe { syscall3(nr::GETSOCKNAME, fd as u64, addr as u64, addrlen as u64) as isize }
 }
 
 pub fn getpeername(fd: i32, addr: *mut u8, addrlen: *mut usize) -> isize {
-    unsafe { syscall3(52, fd as u64, addr as u64, addrlen as u64) as isize }
+    unsafe { syscall3(nr::GETPEERNAME, fd as u64, addr as u64, addrlen as u64) as isize }
+}
+
+/// Matches the kernel's `struct iovec`: a base pointer and a length, used by
+/// `readv`/`writev` to scatter/gather across several buffers in one syscall.
+#[repr(C)]
+pub struct IoVec {
+    pub base: *mut u8,
+    pub len: usize,
+}
+
+/// The kernel caps a single `readv`/`writev`/`preadv`/`pwritev` call at
+/// `UIO_MAXIOV` iovecs; anything past that is silently ignored by the
+/// wrappers below rather than handed to the syscall.
+pub const UIO_MAXIOV: usize = 1024;
+
+/// A borrowed buffer to gather into a single `writev`/`pwritev` call.
+/// `#[repr(transparent)]` over `IoVec` so a `&[IoSlice]` can be passed
+/// straight through as `&[IoVec]`, while the lifetime ties the raw pointer
+/// back to the buffer it came from.
+#[repr(transparent)]
+pub struct IoSlice<'a> {
+    vec: IoVec,
+    _marker: core::marker::PhantomData<&'a [u8]>,
+}
+
+impl<'a> IoSlice<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self {
+            vec: IoVec { base: buf.as_ptr() as *mut u8, len: buf.len() },
+            _marker: core::marker::PhantomData,
+        }
+    }
+}
+
+/// A borrowed buffer to scatter a `readv`/`preadv` call into. Same layout
+/// trick as [`IoSlice`], but over a mutable buffer.
+#[repr(transparent)]
+pub struct IoSliceMut<'a> {
+    vec: IoVec,
+    _marker: core::marker::PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> IoSliceMut<'a> {
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self {
+            vec: IoVec { base: buf.as_mut_ptr(), len: buf.len() },
+            _marker: core::marker::PhantomData,
+        }
+    }
+}
+
+pub fn writev(fd: i32, bufs: &[IoSlice]) -> Result<usize, Errno> {
+    let n = bufs.len().min(UIO_MAXIOV);
+    ret_usize(unsafe {
+        syscall3(nr::WRITEV, fd as u64, bufs.as_ptr() as u64, n as u64) as isize
+    })
+}
+
+pub fn readv(fd: i32, bufs: &mut [IoSliceMut]) -> Result<usize, Errno> {
+    let n = bufs.len().min(UIO_MAXIOV);
+    ret_usize(unsafe {
+        syscall3(nr::READV, fd as u64, bufs.as_ptr() as u64, n as u64) as isize
+    })
+}
+
+/// `writev` at a file offset, leaving the fd's own position untouched -
+/// for future file (rather than socket) use, where `writev` alone would
+/// advance the shared offset.
+pub fn pwritev(fd: i32, bufs: &[IoSlice], offset: i64) -> Result<usize, Errno> {
+    let n = bufs.len().min(UIO_MAXIOV);
+    ret_usize(unsafe {
+        syscall4(nr::PWRITEV, fd as u64, bufs.as_ptr() as u64, n as u64, offset as u64) as isize
+    })
+}
+
+/// `readv` at a file offset; see [`pwritev`].
+pub fn preadv(fd: i32, bufs: &mut [IoSliceMut], offset: i64) -> Result<usize, Errno> {
+    let n = bufs.len().min(UIO_MAXIOV);
+    ret_usize(unsafe {
+        syscall4(nr::PREADV, fd as u64, bufs.as_ptr() as u64, n as u64, offset as u64) as isize
+    })
+}
+
+/// Flags for [`splice`]/[`sendfile`]-style zero-copy moves. A thin newtype
+/// over the raw bitmask rather than pulling in a bitflags crate - combine
+/// with `|` like the raw flag constants (`O_RDWR`, etc.) elsewhere in this
+/// file, just with named variants instead of bare ints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpliceFlags(pub u32);
+
+impl SpliceFlags {
+    pub const NONE: SpliceFlags = SpliceFlags(0);
+    pub const MOVE: SpliceFlags = SpliceFlags(1);
+    pub const NONBLOCK: SpliceFlags = SpliceFlags(2);
+    pub const MORE: SpliceFlags = SpliceFlags(4);
+}
+
+impl core::ops::BitOr for SpliceFlags {
+    type Output = SpliceFlags;
+    fn bitor(self, rhs: SpliceFlags) -> SpliceFlags {
+        SpliceFlags(self.0 | rhs.0)
+    }
+}
+
+/// Create a pipe via `pipe2`, returning `(read_fd, write_fd)`. Used as the
+/// kernel-space bounce buffer `splice` needs, since it can only move data
+/// into or out of a pipe, never directly between two sockets.
+pub fn pipe2(flags: i32) -> Result<(i32, i32), Errno> {
+    let mut fds = [0i32; 2];
+    ret(unsafe { syscall2(nr::PIPE2, fds.as_mut_ptr() as u64, flags as u64) as isize })?;
+    Ok((fds[0], fds[1]))
+}
+
+/// Move up to `len` bytes from `fd_in` to `fd_out` without copying through
+/// userspace. One of the two fds must be a pipe (see [`pipe2`]); offsets are
+/// always `NULL` here since every caller operates on sockets/pipes rather
+/// than seekable files.
+pub fn splice(fd_in: i32, fd_out: i32, len: usize, flags: SpliceFlags) -> Result<usize, Errno> {
+    ret_usize(unsafe {
+        syscall6(
+            nr::SPLICE,
+            fd_in as u64,
+            0,
+            fd_out as u64,
+            0,
+            len as u64,
+            flags.0 as u64,
+        ) as isize
+    })
+}
+
+/// Copy up to `count` bytes from `in_fd` to `out_fd` (a socket) entirely in
+/// kernel space, for the common "serve a file descriptor's contents over a
+/// socket" case that doesn't need a pipe-backed `splice` at all.
+pub fn sendfile(out_fd: i32, in_fd: i32, count: usize) -> Result<usize, Errno> {
+    ret_usize(unsafe {
+        syscall4(nr::SENDFILE, out_fd as u64, in_fd as u64, 0, count as u64) as isize
+    })
+}
+
+/// Matches the kernel's `struct msghdr`, as used by `sendmsg`/`recvmsg`.
+/// `repr(C)` reproduces the same inter-field padding the kernel's C struct
+/// has (e.g. after the `u32 msg_namelen` to keep `msg_iov` pointer-aligned),
+/// so the layout lines up without needing manual padding fields.
+#[repr(C)]
+pub struct MsgHdr {
+    pub msg_name: *mut u8,
+    pub msg_namelen: u32,
+    pub msg_iov: *mut IoVec,
+    pub msg_iovlen: usize,
+    pub msg_control: *mut u8,
+    pub msg_controllen: usize,
+    pub msg_flags: i32,
+}
+
+/// Matches the kernel's `struct cmsghdr` header; the control message's
+/// payload (e.g. the fd array for `SCM_RIGHTS`) follows immediately after,
+/// at `cmsg_align(size_of::<CMsgHdr>())`.
+#[repr(C)]
+pub struct CMsgHdr {
+    pub cmsg_len: usize,
+    pub cmsg_level: i32,
+    pub cmsg_type: i32,
+}
+
+pub const SCM_RIGHTS: i32 = 1;
+/// Set in `MsgHdr::msg_flags` by the kernel when the control buffer was too
+/// small to hold every ancillary message - anything past `msg_controllen`
+/// was silently discarded (not leaked into `buf`, but gone).
+pub const MSG_CTRUNC: i32 = 0x08;
+
+/// Round `len` up to the control message alignment (`size_of::<usize>()`
+/// on every target here), matching the kernel's `CMSG_ALIGN`.
+const fn cmsg_align(len: usize) -> usize {
+    let word = core::mem::size_of::<usize>();
+    (len + word - 1) & !(word - 1)
+}
+
+/// `CMSG_SPACE`: total bytes a control buffer needs to hold one cmsghdr
+/// plus `payload_len` bytes of aligned data.
+fn cmsg_space(payload_len: usize) -> usize {
+    cmsg_align(core::mem::size_of::<CMsgHdr>()) + cmsg_align(payload_len)
+}
+
+/// `CMSG_LEN`: the `cmsg_len` value for a cmsghdr whose payload is
+/// `payload_len` bytes (unlike `cmsg_space`, the payload itself isn't
+/// padded - only the header is).
+fn cmsg_len(payload_len: usize) -> usize {
+    cmsg_align(core::mem::size_of::<CMsgHdr>()) + payload_len
+}
+
+pub fn sendmsg(sock: i32, msg: *const MsgHdr, flags: i32) -> Result<usize, Errno> {
+    ret_usize(unsafe { syscall3(nr::SENDMSG, sock as u64, msg as u64, flags as u64) as isize })
+}
+
+pub fn recvmsg(sock: i32, msg: *mut MsgHdr, flags: i32) -> Result<usize, Errno> {
+    ret_usize(unsafe { syscall3(nr::RECVMSG, sock as u64, msg as u64, flags as u64) as isize })
+}
+
+/// Send `payload` over `sock` (a `AF_UNIX` socket) along with the open fds
+/// in `fds`, encoded as a single `SCM_RIGHTS` control message.
+pub fn send_fds(sock: i32, payload: &[u8], fds: &[i32]) -> Result<usize, Errno> {
+    let fds_len = fds.len() * core::mem::size_of::<i32>();
+    let mut control = vec![0u8; cmsg_space(fds_len)];
+    unsafe {
+        let hdr = control.as_mut_ptr() as *mut CMsgHdr;
+        (*hdr).cmsg_len = cmsg_len(fds_len);
+        (*hdr).cmsg_level = SOL_SOCKET;
+        (*hdr).cmsg_type = SCM_RIGHTS;
+        let data = control
+            .as_mut_ptr()
+            .add(cmsg_align(core::mem::size_of::<CMsgHdr>())) as *mut i32;
+        for (i, &fd) in fds.iter().enumerate() {
+            *data.add(i) = fd;
+        }
+    }
+    let mut iov = [IoVec { base: payload.as_ptr() as *mut u8, len: payload.len() }];
+    let control_len = control.len();
+    let mut msg = MsgHdr {
+        msg_name: core::ptr::null_mut(),
+        msg_namelen: 0,
+        msg_iov: iov.as_mut_ptr(),
+        msg_iovlen: 1,
+        msg_control: control.as_mut_ptr(),
+        msg_controllen: control_len,
+        msg_flags: 0,
+    };
+    sendmsg(sock, &mut msg, 0)
+}
+
+/// The most fds a single [`recv_fds`] call will accept in one `SCM_RIGHTS`
+/// message - bounds the control buffer it allocates up front.
+const MAX_SCM_FDS: usize = 64;
+
+/// Receive into `buf` from `sock`, returning the byte count alongside any
+/// fds the sender attached via `SCM_RIGHTS`. Walks the control buffer like
+/// `CMSG_NXTHDR` would, but never past the `msg_controllen` the kernel
+/// actually reported filling - a cmsghdr claiming to extend beyond that (as
+/// happens when `MSG_CTRUNC` is set because the buffer was too small) is
+/// where the walk stops, rather than reading past what the kernel wrote.
+pub fn recv_fds(sock: i32, buf: &mut [u8]) -> Result<(usize, Vec<i32>), Errno> {
+    let mut control = vec![0u8; cmsg_space(MAX_SCM_FDS * core::mem::size_of::<i32>())];
+    let mut iov = [IoVec { base: buf.as_mut_ptr(), len: buf.len() }];
+    let control_len = control.len();
+    let mut msg = MsgHdr {
+        msg_name: core::ptr::null_mut(),
+        msg_namelen: 0,
+        msg_iov: iov.as_mut_ptr(),
+        msg_iovlen: 1,
+        msg_control: control.as_mut_ptr(),
+        msg_controllen: control_len,
+        msg_flags: 0,
+    };
+    let n = recvmsg(sock, &mut msg, 0)?;
+
+    let mut fds = Vec::new();
+    let hdr_size = cmsg_align(core::mem::size_of::<CMsgHdr>());
+    let mut offset = 0usize;
+    while offset + hdr_size <= msg.msg_controllen {
+        let hdr = unsafe { &*(control.as_ptr().add(offset) as *const CMsgHdr) };
+        if hdr.cmsg_len < hdr_size {
+            break;
+        }
+        let this_end = offset + hdr.cmsg_len;
+        if this_end > msg.msg_controllen {
+            break;
+        }
+        if hdr.cmsg_level == SOL_SOCKET && hdr.cmsg_type == SCM_RIGHTS {
+            let n_fds = (this_end - offset - hdr_size) / core::mem::size_of::<i32>();
+            let data = unsafe { control.as_ptr().add(offset + hdr_size) as *const i32 };
+            for i in 0..n_fds.min(MAX_SCM_FDS - fds.len()) {
+                fds.push(unsafe { *data.add(i) });
+            }
+        }
+        offset += cmsg_align(hdr.cmsg_len);
+    }
+    Ok((n, fds))
 }
 
 // Byte-order helpers
@@ -308,14 +639,23 @@ pub fn ntohs(x: u16) -> u16 {
     u16::from_be(x)
 }
 
-// Clone for thread spawning
+// Clone for thread spawning. The kernel's argument order for the last two
+// slots isn't uniform across architectures: x86_64 takes
+// (flags, stack, ptid, ctid, tls), while aarch64/riscv64 take
+// (flags, stack, ptid, tls, ctid) - ctid and tls are swapped.
+#[cfg(target_arch = "x86_64")]
 pub fn clone(flags: u64, stack: *mut u8, ptid: *mut i32, ctid: *mut i32, tls: u64) -> isize {
-    unsafe { syscall5(56, flags, stack as u64, ptid as u64, ctid as u64, tls) as isize }
+    unsafe { syscall5(nr::CLONE, flags, stack as u64, ptid as u64, ctid as u64, tls) as isize }
+}
+
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+pub fn clone(flags: u64, stack: *mut u8, ptid: *mut i32, ctid: *mut i32, tls: u64) -> isize {
+    unsafe { syscall5(nr::CLONE, flags, stack as u64, ptid as u64, tls, ctid as u64) as isize }
 }
 
 // waitpid wrapper
 pub fn waitpid(pid: i32, status: *mut i32, options: i32) -> isize {
-    unsafe { syscall3(61, pid as u64, status as u64, options as u64) as isize }
+    unsafe { syscall3(nr::WAIT4, pid as u64, status as u64, options as u64) as isize }
 }
 
 // rt_sigaction wrapper. On x86_64 the syscall signature is:
@@ -324,7 +664,7 @@ pub fn waitpid(pid: i32, status: *mut i32, options: i32) -> isize {
 pub fn rt_sigaction(signum: i32, act: *const u8, oldact: *mut u8, sigsetsize: usize) -> isize {
     unsafe {
         syscall4(
-            13,
+            nr::RT_SIGACTION,
             signum as u64,
             act as u64,
             oldact as u64,
@@ -336,7 +676,7 @@ pub fn rt_sigaction(signum: i32, act: *const u8, oldact: *mut u8, sigsetsize: us
 pub fn nanosleep(seconds: u64) -> isize {
     // Legacy wrapper (seconds). Keep for compatibility but prefer nanosleep_ns.
     let ts = [seconds, 0u64];
-    unsafe { syscall2(35, &ts as *const u64 as u64, 0) as isize }
+    unsafe { syscall2(nr::NANOSLEEP, &ts as *const u64 as u64, 0) as isize }
 }
 
 /// Sleep for the given duration in nanoseconds using `nanosleep` syscall.
@@ -344,7 +684,7 @@ pub fn nanosleep_ns(nanos: u64) -> isize {
     let sec = nanos / 1_000_000_000;
     let nsec = (nanos % 1_000_000_000) as u64;
     let ts = [sec, nsec];
-    unsafe { syscall2(35, &ts as *const u64 as u64, 0) as isize }
+    unsafe { syscall2(nr::NANOSLEEP, &ts as *const u64 as u64, 0) as isize }
 }
 
 // Thread-local storage structure (minimal)
@@ -354,6 +694,102 @@ struct TlsBlock {
     _padding: [u64; 15],     // Reserve space for future use
 }
 
+/// Point this thread's TLS base at `tls`. x86_64 has no user-writable base
+/// register, so it needs the `arch_prctl` syscall; aarch64 and riscv64 both
+/// expose a directly writable thread-pointer register (`tpidr_el0`, `tp`)
+/// and need no syscall at all.
+#[cfg(target_arch = "x86_64")]
+fn set_thread_base(tls: *mut u8) {
+    const ARCH_SET_FS: u64 = 0x1002;
+    unsafe {
+        syscall2(nr::ARCH_PRCTL, ARCH_SET_FS, tls as u64);
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+fn set_thread_base(tls: *mut u8) {
+    unsafe {
+        core::arch::asm!("msr tpidr_el0, {val}", val = in(reg) tls as u64);
+    }
+}
+
+#[cfg(target_arch = "riscv64")]
+fn set_thread_base(tls: *mut u8) {
+    unsafe {
+        core::arch::asm!("mv tp, {val}", val = in(reg) tls as u64);
+    }
+}
+
+/// Allocate a fresh TLS block and point this thread's TLS base register at
+/// it. `spawn_thread` already does the clone-time equivalent for worker
+/// threads via `CLONE_SETTLS`; call this explicitly for any thread that
+/// didn't go through `spawn_thread` (e.g. the main thread, when it also
+/// joins in as a worker) before touching `tls_word`/`set_tls_word`.
+pub fn init_thread_tls() {
+    const PROT_RW: i32 = 0x3;
+    const MAP_PRIVATE_ANON: i32 = 0x22;
+    let tls = mmap(0, 4096, PROT_RW, MAP_PRIVATE_ANON);
+    if tls.is_null() {
+        return;
+    }
+    unsafe {
+        let block = tls as *mut TlsBlock;
+        (*block).self_ptr = block;
+    }
+    set_thread_base(tls);
+}
+
+/// Read reserved TLS word `idx` (0..15) for the current thread - used for
+/// genuinely per-thread state, like the cooperative scheduling budget,
+/// since no_std has no `thread_local!` macro to reach for.
+#[cfg(target_arch = "x86_64")]
+pub fn tls_word(idx: usize) -> usize {
+    let byte_off = 8 * (idx + 1); // skip the self-pointer at offset 0
+    let val: u64;
+    unsafe {
+        core::arch::asm!("mov {val}, fs:[{off}]", val = out(reg) val, off = in(reg) byte_off);
+    }
+    val as usize
+}
+
+#[cfg(target_arch = "x86_64")]
+pub fn set_tls_word(idx: usize, value: usize) {
+    let byte_off = 8 * (idx + 1);
+    unsafe {
+        core::arch::asm!("mov fs:[{off}], {val}", off = in(reg) byte_off, val = in(reg) value as u64);
+    }
+}
+
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+pub fn tls_word(idx: usize) -> usize {
+    let byte_off = 8 * (idx + 1);
+    let base: u64;
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        core::arch::asm!("mrs {base}, tpidr_el0", base = out(reg) base);
+    }
+    #[cfg(target_arch = "riscv64")]
+    unsafe {
+        core::arch::asm!("mv {base}, tp", base = out(reg) base);
+    }
+    unsafe { *((base as usize + byte_off) as *const u64) as usize }
+}
+
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+pub fn set_tls_word(idx: usize, value: usize) {
+    let byte_off = 8 * (idx + 1);
+    let base: u64;
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        core::arch::asm!("mrs {base}, tpidr_el0", base = out(reg) base);
+    }
+    #[cfg(target_arch = "riscv64")]
+    unsafe {
+        core::arch::asm!("mv {base}, tp", base = out(reg) base);
+    }
+    unsafe { *((base as usize + byte_off) as *mut u64) = value as u64 };
+}
+
 pub fn spawn_thread(f: extern "C" fn(*mut u8), arg: *mut u8, stack_size: usize) -> Result<(), i32> {
     const PROT_RW: i32 = 0x3;
     const MAP_PRIVATE_ANON: i32 = 0x22;
@@ -383,6 +819,13 @@ pub fn spawn_thread(f: extern "C" fn(*mut u8), arg: *mut u8, stack_size: usize)
     // 0x80000 = CLONE_SETTLS: set TLS pointer to avoid segfaults
     const FLAGS: u64 = 0x100 | 0x200 | 0x400 | 0x800 | 0x10000 | 0x80000;
 
+    // `clone()` already reorders the ctid/tls args for the target arch, and
+    // CLONE_SETTLS makes the kernel itself point the child's TLS base
+    // register at `tls` before it returns - no arch-specific trampoline
+    // needed here. The one thing that IS arch-specific is implicit: each
+    // `syscallN` in the child resumes at the instruction right after the
+    // clone syscall, on whatever stack pointer `stack_top` put it on, which
+    // holds for the syscall/svc/ecall instructions on all three targets.
     let ret = clone(
         FLAGS,
         stack_top,
@@ -27,6 +27,43 @@ pub fn find_header_value<'a>(req: &'a [u8], name: &str) -> Option<&'a [u8]> {
     None
 }
 
+/// XOR `payload` in place with the 4-byte WebSocket masking `key`, repeating
+/// the key every 4 bytes (RFC 6455 §5.3). Shared by the unmask step below
+/// (server receiving a masked frame from a client) and by client-side frame
+/// masking before a send.
+pub fn apply_mask(payload: &mut [u8], key: [u8; 4]) {
+    for (i, b) in payload.iter_mut().enumerate() {
+        *b ^= key[i & 3];
+    }
+}
+
+/// Peek the payload length a WebSocket frame header declares, without
+/// requiring the full frame (payload included) to be buffered yet, unlike
+/// `parse_websocket_frame`. Lets a caller reject an oversized frame before
+/// buffering its payload.
+pub fn peek_frame_payload_len(buf: &[u8]) -> Option<usize> {
+    if buf.len() < 2 {
+        return None;
+    }
+    let mut payload_len = (buf[1] & 0x7f) as usize;
+    let pos = 2usize;
+    if payload_len == 126 {
+        if buf.len() < pos + 2 {
+            return None;
+        }
+        payload_len = ((buf[pos] as usize) << 8) | (buf[pos + 1] as usize);
+    } else if payload_len == 127 {
+        if buf.len() < pos + 8 {
+            return None;
+        }
+        payload_len = 0usize;
+        for i in 0..8 {
+            payload_len = (payload_len << 8) | (buf[pos + i] as usize);
+        }
+    }
+    Some(payload_len)
+}
+
 /// Parse WebSocket frame, returns (bytes_consumed, fin, opcode, payload)
 pub fn parse_websocket_frame(buf: &[u8]) -> Option<(usize, bool, u8, Vec<u8>)> {
     if buf.len() < 2 {
@@ -76,15 +113,192 @@ pub fn parse_websocket_frame(buf: &[u8]) -> Option<(usize, bool, u8, Vec<u8>)> {
         payload.extend_from_slice(&buf[pos..pos + payload_len]);
     }
     if masked {
-        let key = &buf[mask_key_pos..mask_key_pos + 4];
-        for i in 0..payload_len {
-            payload[i] ^= key[i & 3];
-        }
+        let key = [
+            buf[mask_key_pos],
+            buf[mask_key_pos + 1],
+            buf[mask_key_pos + 2],
+            buf[mask_key_pos + 3],
+        ];
+        apply_mask(&mut payload, key);
     }
     
     Some((frame_total, fin, opcode, payload))
 }
 
+/// Maximum bytes a decoded chunked body may accumulate to before the caller
+/// should give up (protects the bump allocator from unbounded growth).
+pub const MAX_CHUNKED_BODY: usize = 1024 * 1024;
+
+/// Outcome of scanning a single chunk-size line.
+enum ChunkSizeLine {
+    /// `(size, bytes_consumed_including_crlf)`.
+    Parsed(usize, usize),
+    /// `buf` doesn't yet hold a full line; the caller should recv more.
+    Incomplete,
+    /// `buf` already holds a full line and it isn't a valid chunk-size line.
+    Malformed,
+}
+
+/// Parse a hex chunk-size line (digits optionally followed by `;ext`) ending
+/// in CRLF.
+fn parse_chunk_size_line(buf: &[u8]) -> ChunkSizeLine {
+    let mut i = 0;
+    let mut size: usize = 0;
+    let mut any = false;
+    while i < buf.len() {
+        let c = buf[i];
+        let digit = match c {
+            b'0'..=b'9' => Some(c - b'0'),
+            b'a'..=b'f' => Some(c - b'a' + 10),
+            b'A'..=b'F' => Some(c - b'A' + 10),
+            _ => None,
+        };
+        match digit {
+            Some(d) => {
+                size = match size.checked_mul(16).and_then(|s| s.checked_add(d as usize)) {
+                    Some(s) => s,
+                    // Hex value too large to fit a usize - no amount of
+                    // further data makes this line valid.
+                    None => return ChunkSizeLine::Malformed,
+                };
+                any = true;
+                i += 1;
+            }
+            None => break,
+        }
+    }
+    if !any {
+        // No valid leading hex digit. A line that already has its
+        // terminating CRLF in `buf` is complete and definitively malformed;
+        // one that doesn't yet might just be waiting on the rest to arrive.
+        let mut j = i;
+        while j + 1 < buf.len() && !(buf[j] == b'\r' && buf[j + 1] == b'\n') {
+            j += 1;
+        }
+        return if j + 1 < buf.len() { ChunkSizeLine::Malformed } else { ChunkSizeLine::Incomplete };
+    }
+    // Skip `;ext` up to CRLF.
+    while i < buf.len() && buf[i] != b'\r' {
+        i += 1;
+    }
+    if i + 1 >= buf.len() || buf[i] != b'\r' || buf[i + 1] != b'\n' {
+        return ChunkSizeLine::Incomplete;
+    }
+    ChunkSizeLine::Parsed(size, i + 2)
+}
+
+/// Why `parse_chunked` couldn't hand back a decoded chunk.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChunkedError {
+    /// Declared or accumulated chunk data would exceed `max_size`; the
+    /// caller should reject the request rather than recv more.
+    TooLarge,
+    /// A chunk-size line overflowed `usize` or was otherwise malformed, or a
+    /// chunk's data wasn't followed by CRLF; the caller should reject the
+    /// request.
+    Malformed,
+}
+
+/// Decode an RFC 7230 §4.1 chunked body from `buf`.
+///
+/// Returns `Ok(Some((consumed, decoded, complete)))` where `consumed` is the
+/// number of bytes of `buf` that made up the chunks parsed so far, `decoded`
+/// is the reassembled payload, and `complete` indicates the terminating
+/// 0-length chunk (plus trailers and the final CRLF) was seen. Returns
+/// `Ok(None)` if `buf` doesn't yet contain a full chunk, so the caller should
+/// recv more data and retry. Returns `Err(ChunkedError)` if the body should
+/// be rejected outright instead - either `decoded` would exceed `max_size`,
+/// or the input is malformed - so the caller never needs its own
+/// independent size cap.
+pub fn parse_chunked(
+    buf: &[u8],
+    max_size: usize,
+) -> Result<Option<(usize, Vec<u8>, bool)>, ChunkedError> {
+    // Out of input: hand back the chunks decoded so far, or `None` if not
+    // even one full chunk has been parsed yet.
+    fn partial(pos: usize, decoded: Vec<u8>) -> Option<(usize, Vec<u8>, bool)> {
+        if pos == 0 { None } else { Some((pos, decoded, false)) }
+    }
+    let mut pos = 0usize;
+    let mut decoded = Vec::new();
+    loop {
+        let (size, hdr_len) = match parse_chunk_size_line(&buf[pos..]) {
+            ChunkSizeLine::Parsed(size, hdr_len) => (size, hdr_len),
+            ChunkSizeLine::Incomplete => return Ok(partial(pos, decoded)),
+            ChunkSizeLine::Malformed => return Err(ChunkedError::Malformed),
+        };
+        let chunk_start = pos + hdr_len;
+        if size == 0 {
+            // Last chunk: consume trailer headers (if any) up to the blank line.
+            let mut trailer_end = chunk_start;
+            loop {
+                if trailer_end + 1 >= buf.len() {
+                    return Ok(partial(pos, decoded));
+                }
+                if buf[trailer_end] == b'\r' && buf[trailer_end + 1] == b'\n' {
+                    trailer_end += 2;
+                    break;
+                }
+                // advance to the next line
+                while trailer_end + 1 < buf.len()
+                    && !(buf[trailer_end] == b'\r' && buf[trailer_end + 1] == b'\n')
+                {
+                    trailer_end += 1;
+                }
+                if trailer_end + 1 >= buf.len() {
+                    return Ok(partial(pos, decoded));
+                }
+                trailer_end += 2;
+            }
+            return Ok(Some((trailer_end, decoded, true)));
+        }
+        if decoded.len() + size > max_size {
+            return Err(ChunkedError::TooLarge);
+        }
+        let data_end = chunk_start + size;
+        if buf.len() < data_end + 2 {
+            return Ok(partial(pos, decoded));
+        }
+        if buf[data_end] != b'\r' || buf[data_end + 1] != b'\n' {
+            return Err(ChunkedError::Malformed);
+        }
+        decoded.extend_from_slice(&buf[chunk_start..data_end]);
+        pos = data_end + 2;
+    }
+}
+
+/// Encode `payload` as a single RFC 7230 §4.1 chunk followed by the
+/// terminating zero-length chunk.
+pub fn build_chunked(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    if !payload.is_empty() {
+        let (hex, len) = hex_len(payload.len());
+        out.extend_from_slice(&hex[..len]);
+        out.extend_from_slice(b"\r\n");
+        out.extend_from_slice(payload);
+        out.extend_from_slice(b"\r\n");
+    }
+    out.extend_from_slice(b"0\r\n\r\n");
+    out
+}
+
+fn hex_len(mut n: usize) -> ([u8; 16], usize) {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut buf = [0u8; 16];
+    if n == 0 {
+        buf[0] = b'0';
+        return (buf, 1);
+    }
+    let mut i = 0;
+    while n > 0 {
+        buf[i] = DIGITS[n & 0xf];
+        n >>= 4;
+        i += 1;
+    }
+    buf[..i].reverse();
+    (buf, i)
+}
+
 /// Build WebSocket frame (unmasked, server->client)
 pub fn build_websocket_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
     let mut out = Vec::new();
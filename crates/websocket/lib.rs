@@ -1,4 +1,4 @@
-//! WebSocket server implementation
+//! WebSocket server and client implementation
 
 #![no_std]
 
@@ -10,35 +10,424 @@ use async_utils::{crypto, parsing};
 
 const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
 
-/// Public API: Accept WebSocket connection
-pub async fn accept_connection(fd: i32, request: &[u8]) {
-    accept_and_run(fd, request).await
+/// Tunable frame/message size limits for a [`WsCodec`], so embedders can
+/// bound how much memory a connection is allowed to make it allocate.
+/// Defaults match what common WebSocket implementations use.
+#[derive(Clone, Copy, Debug)]
+pub struct WsLimits {
+    /// Largest payload length a single frame's header may declare.
+    pub max_frame_size: usize,
+    /// Largest total size of a reassembled (possibly fragmented) message.
+    pub max_message_size: usize,
+}
+
+impl Default for WsLimits {
+    fn default() -> Self {
+        Self { max_frame_size: 64 * 1024, max_message_size: 64 * 1024 }
+    }
+}
+
+/// A fully reassembled WebSocket message, decoded from the wire by
+/// [`WsCodec::decode`] and handed to [`WsCodec::encode`] to go back out.
+pub enum Message {
+    Text(Vec<u8>),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    /// A close frame's optional status code and UTF-8 reason, per
+    /// RFC 6455 §5.5.1 (`None` if the peer sent an empty close frame).
+    Close(Option<(u16, Vec<u8>)>),
+}
+
+/// Standard WebSocket close status codes (RFC 6455 §7.4.1).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CloseCode {
+    Normal = 1000,
+    ProtocolError = 1002,
+    InvalidData = 1003,
+    InvalidPayloadData = 1007,
+    PolicyViolation = 1008,
+    MessageTooBig = 1009,
+    Unexpected = 1011,
 }
 
-async fn send_ws_payload(fd: i32, payload: &[u8]) {
-    // Build a single unmasked text frame (server -> client)
+impl CloseCode {
+    fn to_be_bytes(self) -> [u8; 2] {
+        (self as u16).to_be_bytes()
+    }
+
+    /// Map a status code received from a peer back onto one of our known
+    /// codes, so we always reply with something we recognize.
+    fn from_peer(code: u16) -> CloseCode {
+        match code {
+            1000 => CloseCode::Normal,
+            1002 => CloseCode::ProtocolError,
+            1003 => CloseCode::InvalidData,
+            1007 => CloseCode::InvalidPayloadData,
+            1008 => CloseCode::PolicyViolation,
+            1009 => CloseCode::MessageTooBig,
+            1011 => CloseCode::Unexpected,
+            // Any other code (including the reserved/private ranges) is
+            // treated as a normal close; we have nothing more specific to
+            // say back to the peer.
+            _ => CloseCode::Normal,
+        }
+    }
+}
+
+/// Parse the client's `Sec-WebSocket-Protocol` header, a comma-separated
+/// list offered in the client's preference order, and return the first
+/// entry that's also in `supported` (checked in that offered order).
+fn negotiate_subprotocol<'a>(request: &[u8], supported: &[&'a str]) -> Option<&'a str> {
+    let offered = parsing::find_header_value(request, "Sec-WebSocket-Protocol")?;
+    let offered = core::str::from_utf8(offered).ok()?;
+    for candidate in offered.split(',') {
+        let candidate = candidate.trim();
+        if let Some(&matched) = supported.iter().find(|&&s| s == candidate) {
+            return Some(matched);
+        }
+    }
+    None
+}
+
+/// Check how much of `data` is valid UTF-8, tolerating a truncated
+/// multi-byte sequence at the very end (up to 3 bytes, the most a UTF-8
+/// sequence can have left once its first byte is known). Returns
+/// `Some(valid_len)`, the prefix length safe to accept now - the rest
+/// (`data.len() - valid_len`) should be carried into the next fragment and
+/// re-checked once more bytes arrive. Returns `None` if `data` contains an
+/// outright invalid sequence, not just a truncated one.
+fn utf8_valid_prefix_len(data: &[u8]) -> Option<usize> {
+    match core::str::from_utf8(data) {
+        Ok(_) => Some(data.len()),
+        Err(e) => match e.error_len() {
+            Some(_) => None,
+            // `None` means the error is "ran out of bytes" at the tail,
+            // i.e. a sequence that may yet complete with more fragments.
+            None => Some(e.valid_up_to()),
+        },
+    }
+}
+
+/// A tiny xorshift64 step; callers seed it from the monotonic clock mixed
+/// with a per-call value (a pointer address when masking a frame, the fd
+/// when generating a handshake nonce) - not cryptographic, but enough spread for frame
+/// masking, where the only requirement is that a passive observer can't
+/// predict the mask from the payload alone. Mirrors the PRNG the scheduler
+/// uses to pick a work-stealing victim.
+fn xorshift_next(seed: &mut u64) -> u64 {
+    let mut x = *seed;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    *seed = x;
+    x
+}
+
+/// Build a masked frame (client -> server direction, RFC 6455 §5.3): same
+/// layout as [`parsing::build_websocket_frame`] but with the mask bit set
+/// and a 4-byte mask inserted before the (XORed) payload.
+fn build_masked_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
     let mut out = Vec::new();
-    out.push(0x80 | 0x2); // FIN + binary opcode (client expects ArrayBuffer)
+    out.push(0x80 | opcode);
     let l = payload.len();
     if l < 126 {
-        out.push(l as u8);
+        out.push(0x80 | (l as u8));
     } else if l < 65536 {
-        out.push(126);
+        out.push(0x80 | 126);
         out.push(((l >> 8) & 0xff) as u8);
         out.push((l & 0xff) as u8);
     } else {
-        out.push(127);
+        out.push(0x80 | 127);
         for i in (0..8).rev() {
             out.push(((l >> (i * 8)) & 0xff) as u8);
         }
     }
-    out.extend_from_slice(payload);
 
-    // Ensure we send the entire frame, handling partial writes
+    let mut seed = sys::clock_gettime_monotonic_ns() ^ (out.as_ptr() as u64);
+    let mask = (xorshift_next(&mut seed) as u32).to_ne_bytes();
+    out.extend_from_slice(&mask);
+    let mut masked_payload = payload.to_vec();
+    parsing::apply_mask(&mut masked_payload, mask);
+    out.extend_from_slice(&masked_payload);
+    out
+}
+
+/// Decodes a byte stream into [`Message`]s and encodes them back out,
+/// keeping frame parsing, fragmentation reassembly, and masking separate
+/// from any particular connection's echo/ping/close policy.
+pub struct WsCodec {
+    frag_opcode: Option<u8>,
+    frag_payload: Vec<u8>,
+    /// Trailing bytes of an incomplete UTF-8 sequence from the most recent
+    /// text fragment, carried forward to be validated once the rest of the
+    /// sequence arrives in the next fragment. Only meaningful while
+    /// `frag_opcode == Some(0x1)`.
+    frag_utf8_carry: Vec<u8>,
+    /// `true` for the server side of a connection (outbound frames are
+    /// sent unmasked); `false` for the client side (outbound frames must
+    /// be masked per RFC 6455 §5.3).
+    server: bool,
+    limits: WsLimits,
+}
+
+impl WsCodec {
+    pub fn new(server: bool) -> Self {
+        Self::with_limits(server, WsLimits::default())
+    }
+
+    pub fn with_limits(server: bool, limits: WsLimits) -> Self {
+        Self {
+            frag_opcode: None,
+            frag_payload: Vec::new(),
+            frag_utf8_carry: Vec::new(),
+            server,
+            limits,
+        }
+    }
+
+    /// Parse and consume complete frames from the front of `buf`, feeding
+    /// data frames through fragmentation reassembly, and return the first
+    /// complete `Message` found. Returns `None` once `buf` holds nothing
+    /// but a partial frame; call again after more bytes have been
+    /// appended to pick up where this left off.
+    ///
+    /// Rejects (with a `1009` close) a single frame whose header declares a
+    /// payload longer than `limits.max_frame_size` as soon as the header is
+    /// available, before its payload has to be buffered in full.
+    pub fn decode(&mut self, buf: &mut Vec<u8>) -> Option<Message> {
+        loop {
+            if let Some(declared_len) = parsing::peek_frame_payload_len(buf) {
+                if declared_len > self.limits.max_frame_size {
+                    return Some(Message::Close(Some((
+                        CloseCode::MessageTooBig as u16,
+                        Vec::from(&b"frame exceeds max_frame_size"[..]),
+                    ))));
+                }
+            }
+
+            let (consumed, fin, opcode, payload) = parsing::parse_websocket_frame(buf)?;
+            let _ = buf.drain(0..consumed);
+
+            // Control frames (0x8 close, 0x9 ping, 0xA pong) must not be
+            // fragmented and must fit in a single frame <= 125 bytes.
+            if opcode >= 0x8 {
+                if !fin || payload.len() > 125 {
+                    return Some(Message::Close(Some((
+                        CloseCode::ProtocolError as u16,
+                        Vec::from(&b"fragmented or oversized control frame"[..]),
+                    ))));
+                }
+                match opcode {
+                    0x8 => {
+                        let parsed = if payload.len() >= 2 {
+                            let code = u16::from_be_bytes([payload[0], payload[1]]);
+                            Some((code, payload[2..].to_vec()))
+                        } else {
+                            None
+                        };
+                        return Some(Message::Close(parsed));
+                    }
+                    0x9 => return Some(Message::Ping(payload)),
+                    0xA => return Some(Message::Pong(payload)),
+                    _ => continue, // reserved control opcode; ignore and keep parsing
+                }
+            }
+
+            if opcode == 0x0 {
+                // Continuation frame.
+                if self.frag_opcode.is_none() {
+                    // Unexpected continuation with nothing open; ignore.
+                    continue;
+                }
+                if self.frag_payload.len() + payload.len() > self.limits.max_message_size {
+                    self.frag_opcode = None;
+                    self.frag_payload.clear();
+                    self.frag_utf8_carry.clear();
+                    return Some(Message::Close(Some((
+                        CloseCode::MessageTooBig as u16,
+                        Vec::from(&b"message exceeds max_message_size"[..]),
+                    ))));
+                }
+                if self.frag_opcode == Some(0x1) {
+                    if self.push_text_fragment(payload).is_err() {
+                        return Some(Self::invalid_utf8_close());
+                    }
+                } else {
+                    self.frag_payload.extend_from_slice(&payload);
+                }
+                if fin {
+                    if !self.frag_utf8_carry.is_empty() {
+                        // Message ended mid-sequence; never completed.
+                        self.frag_opcode = None;
+                        self.frag_payload.clear();
+                        self.frag_utf8_carry.clear();
+                        return Some(Self::invalid_utf8_close());
+                    }
+                    let op = self.frag_opcode.take().unwrap();
+                    let data = core::mem::take(&mut self.frag_payload);
+                    return Some(Self::data_message(op, data));
+                }
+                continue;
+            }
+
+            if fin {
+                if opcode == 0x1 && core::str::from_utf8(&payload).is_err() {
+                    return Some(Self::invalid_utf8_close());
+                }
+                return Some(Self::data_message(opcode, payload));
+            }
+
+            // Opening frame of a fragmented message.
+            self.frag_opcode = Some(opcode);
+            self.frag_payload.clear();
+            self.frag_utf8_carry.clear();
+            if opcode == 0x1 {
+                if self.push_text_fragment(payload).is_err() {
+                    self.frag_opcode = None;
+                    return Some(Self::invalid_utf8_close());
+                }
+            } else {
+                self.frag_payload.extend_from_slice(&payload);
+            }
+        }
+    }
+
+    /// Validate `fragment` against `self.frag_utf8_carry` (the tail of an
+    /// incomplete sequence from the previous fragment), append the now-valid
+    /// bytes to `frag_payload`, and keep any still-incomplete tail for the
+    /// next fragment. `Err(())` means `fragment` contained outright invalid
+    /// UTF-8, not just a boundary split.
+    fn push_text_fragment(&mut self, fragment: Vec<u8>) -> Result<(), ()> {
+        let mut check_buf = core::mem::take(&mut self.frag_utf8_carry);
+        check_buf.extend_from_slice(&fragment);
+        let valid_len = utf8_valid_prefix_len(&check_buf).ok_or(())?;
+        self.frag_utf8_carry = check_buf[valid_len..].to_vec();
+        check_buf.truncate(valid_len);
+        self.frag_payload.extend_from_slice(&check_buf);
+        Ok(())
+    }
+
+    fn invalid_utf8_close() -> Message {
+        Message::Close(Some((
+            CloseCode::InvalidPayloadData as u16,
+            Vec::from(&b"invalid UTF-8 in text frame"[..]),
+        )))
+    }
+
+    fn data_message(opcode: u8, data: Vec<u8>) -> Message {
+        if opcode == 0x1 { Message::Text(data) } else { Message::Binary(data) }
+    }
+
+    /// Build the wire bytes for `msg`, masking them when this codec is on
+    /// the client side of the connection.
+    pub fn encode(&self, msg: &Message) -> Vec<u8> {
+        let (opcode, payload) = match msg {
+            Message::Text(d) => (0x1, d.clone()),
+            Message::Binary(d) => (0x2, d.clone()),
+            Message::Ping(d) => (0x9, d.clone()),
+            Message::Pong(d) => (0xA, d.clone()),
+            Message::Close(Some((code, reason))) => {
+                let mut p = Vec::with_capacity(2 + reason.len());
+                p.extend_from_slice(&code.to_be_bytes());
+                p.extend_from_slice(reason);
+                (0x8, p)
+            }
+            Message::Close(None) => (0x8, Vec::new()),
+        };
+        if self.server {
+            parsing::build_websocket_frame(opcode, &payload)
+        } else {
+            build_masked_frame(opcode, &payload)
+        }
+    }
+}
+
+/// Why a client-side handshake failed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConnectError {
+    WriteFailed,
+    ConnectionClosed,
+    BadStatus,
+    AcceptMismatch,
+}
+
+/// Public API: Accept WebSocket connection
+pub async fn accept_connection(fd: i32, request: &[u8]) {
+    accept_and_run(fd, request).await
+}
+
+/// Perform the client side of the WebSocket opening handshake (RFC 6455
+/// §4.1) over an already-connected socket `fd`: generate a random 16-byte
+/// nonce, send the GET upgrade request for `path` against `host`, and
+/// verify the server's `Sec-WebSocket-Accept` before handing back a
+/// client-mode [`WsCodec`] (outbound frames from it are masked).
+pub async fn connect(fd: i32, host: &str, path: &str) -> Result<WsCodec, ConnectError> {
+    let mut seed = sys::clock_gettime_monotonic_ns() ^ (fd as u64);
+    let mut nonce = [0u8; 16];
+    for chunk in nonce.chunks_mut(8) {
+        let bytes = xorshift_next(&mut seed).to_ne_bytes();
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+    let key = crypto::base64_encode(&nonce);
+
+    let mut req = Vec::new();
+    req.extend_from_slice(b"GET ");
+    req.extend_from_slice(path.as_bytes());
+    req.extend_from_slice(b" HTTP/1.1\r\nHost: ");
+    req.extend_from_slice(host.as_bytes());
+    req.extend_from_slice(
+        b"\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: ",
+    );
+    req.extend_from_slice(key.as_bytes());
+    req.extend_from_slice(b"\r\nSec-WebSocket-Version: 13\r\n\r\n");
+
     let mut off = 0usize;
-    while off < out.len() {
-        let slice = &out[off..];
-        let r = SendFuture::new(fd, slice).await;
+    while off < req.len() {
+        let r = SendFuture::new(fd, &req[off..]).await;
+        if r < 0 {
+            return Err(ConnectError::WriteFailed);
+        }
+        let wrote = r as usize;
+        if wrote == 0 {
+            return Err(ConnectError::WriteFailed);
+        }
+        off += wrote;
+    }
+
+    let mut resp: Vec<u8> = Vec::new();
+    loop {
+        if let Some(header_end) = resp.windows(4).position(|w| w == b"\r\n\r\n") {
+            let headers = &resp[..header_end];
+            if !headers.starts_with(b"HTTP/1.1 101") && !headers.starts_with(b"HTTP/1.0 101") {
+                return Err(ConnectError::BadStatus);
+            }
+            let accept = parsing::find_header_value(headers, "Sec-WebSocket-Accept")
+                .ok_or(ConnectError::AcceptMismatch)?;
+
+            let mut combined = Vec::from(key.as_bytes());
+            combined.extend_from_slice(WS_GUID.as_bytes());
+            let digest = crypto::sha1(&combined);
+            let expected = crypto::base64_encode(&digest);
+            if accept != expected.as_bytes() {
+                return Err(ConnectError::AcceptMismatch);
+            }
+            return Ok(WsCodec::new(false));
+        }
+
+        let chunk = RecvFuture::new(fd, 4096).await;
+        if chunk.is_empty() {
+            return Err(ConnectError::ConnectionClosed);
+        }
+        resp.extend_from_slice(&chunk);
+    }
+}
+
+async fn send_message(fd: i32, codec: &WsCodec, msg: &Message) {
+    let frame = codec.encode(msg);
+    let mut off = 0usize;
+    while off < frame.len() {
+        let r = SendFuture::new(fd, &frame[off..]).await;
         if r < 0 {
             break;
         }
@@ -52,6 +441,29 @@ async fn send_ws_payload(fd: i32, payload: &[u8]) {
 }
 
 pub async fn accept_and_run(fd: i32, request: &[u8]) {
+    accept_and_run_with_limits(fd, request, WsLimits::default()).await
+}
+
+/// Same as [`accept_and_run`], but with caller-chosen frame/message size
+/// limits instead of [`WsLimits::default`].
+pub async fn accept_and_run_with_limits(fd: i32, request: &[u8], limits: WsLimits) {
+    accept_and_run_with_options(fd, request, limits, &[], false).await
+}
+
+/// Same as [`accept_and_run_with_limits`], additionally negotiating a
+/// subprotocol out of the client's `Sec-WebSocket-Protocol` header against
+/// `supported_protocols` (checked in the client's offered order). If the
+/// client offered protocols but none matched `supported_protocols`,
+/// `reject_unmatched_protocol` decides whether to fail the handshake with
+/// an HTTP error response (`true`) or proceed without a
+/// `Sec-WebSocket-Protocol` response header (`false`).
+pub async fn accept_and_run_with_options(
+    fd: i32,
+    request: &[u8],
+    limits: WsLimits,
+    supported_protocols: &[&str],
+    reject_unmatched_protocol: bool,
+) {
     async_runtime::log_write(b"[WS] fd=");
     sys::write_usize(
         async_runtime::LOG_FD.load(core::sync::atomic::Ordering::Relaxed),
@@ -61,6 +473,16 @@ pub async fn accept_and_run(fd: i32, request: &[u8]) {
 
     // find Sec-WebSocket-Key header
     if let Some(key_bytes) = parsing::find_header_value(request, "Sec-WebSocket-Key") {
+        let offered_protocols = parsing::find_header_value(request, "Sec-WebSocket-Protocol");
+        let protocol = negotiate_subprotocol(request, supported_protocols);
+        if reject_unmatched_protocol && offered_protocols.is_some() && protocol.is_none() {
+            let resp = b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\n\r\n";
+            let _ = SendFuture::new(fd, resp).await;
+            async_runtime::unregister_fd(fd);
+            let _ = sys::close(fd);
+            return;
+        }
+
         // trim whitespace
         let mut key_trim = key_bytes;
         while !key_trim.is_empty() && key_trim[0] == b' ' {
@@ -78,6 +500,10 @@ pub async fn accept_and_run(fd: i32, request: &[u8]) {
         let mut resp = Vec::new();
         resp.extend_from_slice(b"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: ");
         resp.extend_from_slice(accept.as_bytes());
+        if let Some(chosen) = protocol {
+            resp.extend_from_slice(b"\r\nSec-WebSocket-Protocol: ");
+            resp.extend_from_slice(chosen.as_bytes());
+        }
         resp.extend_from_slice(b"\r\n\r\n");
         // Log the request line (first CRLF) to aid debugging
         // (debug logging removed)
@@ -100,18 +526,19 @@ pub async fn accept_and_run(fd: i32, request: &[u8]) {
             }
             let ptr = unsafe { resp.as_ptr().add(off_sync) };
             let rem = resp.len() - off_sync;
-            let r = sys::sendto(fd, ptr, rem, 0, core::ptr::null(), 0);
-            if r == -11 {
-                // EAGAIN in blocking mode shouldn't happen
-                continue;
-            }
-            if r < 0 {
-                // Error: fall back to async send
-                let _ = sys::fcntl(fd, sys::F_SETFL, sys::O_NONBLOCK);
-                let _ = SendFuture::new(fd, &resp[off_sync..]).await;
-                break;
-            }
-            let wrote = r as usize;
+            let wrote = match sys::sendto(fd, ptr, rem, 0, core::ptr::null(), 0) {
+                Ok(n) => n,
+                Err(sys::Errno::EAGAIN) => {
+                    // EAGAIN in blocking mode shouldn't happen
+                    continue;
+                }
+                Err(_) => {
+                    // Error: fall back to async send
+                    let _ = sys::fcntl(fd, sys::F_SETFL, sys::O_NONBLOCK);
+                    let _ = SendFuture::new(fd, &resp[off_sync..]).await;
+                    break;
+                }
+            };
             if wrote == 0 {
                 // Unexpected zero: switch to async
                 let _ = sys::fcntl(fd, sys::F_SETFL, sys::O_NONBLOCK);
@@ -136,6 +563,15 @@ pub async fn accept_and_run(fd: i32, request: &[u8]) {
             fd as usize,
         );
         async_runtime::log_write(b" handshake complete\n");
+        if let Some(chosen) = protocol {
+            // Surfaced here so a caller building a non-echo handler on this
+            // loop can branch on which subprotocol was negotiated.
+            async_runtime::log_write(b"[WS] negotiated protocol=");
+            async_runtime::log_write(chosen.as_bytes());
+            async_runtime::log_write(b"\n");
+        }
+
+        let mut codec = WsCodec::with_limits(true, limits);
 
         // Send welcome message with ANSI colors
         let welcome = b"\r\n\x1b[1;32m=== Async NoStd Terminal ===\x1b[0m\r\n\r\n\
@@ -146,13 +582,17 @@ Features:\r\n\
   \x1b[32m*\x1b[0m WebSocket echo server\r\n\
   \x1b[32m*\x1b[0m 32KB binary size\r\n\r\n\
 Type anything to see it echoed back!\r\n\r\n";
-        send_ws_payload(fd, welcome).await;
+        send_message(fd, &codec, &Message::Binary(welcome.to_vec())).await;
 
-        // enter buffered frame loop: accumulate recv bytes and parse frames incrementally.
+        // enter buffered frame loop: accumulate recv bytes and decode
+        // messages incrementally, deciding policy (echo, pong, close) on
+        // each one. All frame parsing/fragmentation/masking lives in
+        // `codec`; this loop only reacts to what it decodes.
         let mut buf_acc: Vec<u8> = Vec::new();
-        // fragmentation state
-        let mut frag_opcode: Option<u8> = None;
-        let mut frag_payload: Vec<u8> = Vec::new();
+        // Monotonic time (ns) of the most recent pong, comparable against a
+        // deadline to detect a stale peer; it is only logged here, and no
+        // idle timeout acts on it.
+        let mut last_pong_ns: u64 = 0;
 
         loop {
             let chunk = RecvFuture::new(fd, 4096).await;
@@ -180,75 +620,48 @@ Type anything to see it echoed back!\r\n\r\n";
             async_runtime::log_write(b" bytes\n");
             buf_acc.extend_from_slice(&chunk);
 
-            // parse as many frames as available
-            let mut parsed_any = false;
-            while let Some((consumed, fin, opcode, payload)) =
-                parsing::parse_websocket_frame(&buf_acc)
-            {
-                // remove consumed bytes
-                let _ = buf_acc.drain(0..consumed);
-                parsed_any = true;
-
-                        // handle fragmentation
-                        if opcode == 0x0 {
-                            // continuation
-                            if frag_opcode.is_none() {
-                                // unexpected continuation, ignore
-                                continue;
-                            }
-                            frag_payload.extend_from_slice(&payload);
-                            if fin {
-                                // finalize
-                                let op = frag_opcode.take().unwrap();
-                                let full = core::mem::take(&mut frag_payload);
-                                // echo as text/binary based on op
-                                if op == 0x1 || op == 0x2 {
-                                    // echo back
-                                    send_ws_payload(fd, &full).await;
-                                    // Send ping to keep connection alive
-                                    let ping = [0x80 | 0x9, 0x00]; // FIN + ping opcode, 0 length
-                                    let _ = SendFuture::new(fd, &ping).await;
-                                }
-                            }
-                            continue;
-                        }
-
-                        if opcode == 0x1 || opcode == 0x2 {
-                            if fin {
-                                // single-frame message — echo
-                                send_ws_payload(fd, &payload).await;
-                                // Send ping to keep connection alive
-                                let ping = [0x80 | 0x9, 0x00]; // FIN + ping opcode, 0 length
-                                let _ = SendFuture::new(fd, &ping).await;
-                            } else {
-                                // start fragmentation
-                                frag_opcode = Some(opcode);
-                                frag_payload.clear();
-                                frag_payload.extend_from_slice(&payload);
-                            }
-                            continue;
-                        }
-
-                        match opcode {
-                            0x8 => {
-                                // close
-                                async_runtime::unregister_fd(fd);
-                                let _ = sys::close(fd);
-                                return;
-                            }
-                            0x9 => {
-                                // ping -> pong (opcode 0xA)
-                                let pong = parsing::build_websocket_frame(0xA, &payload);
-                                let _ = SendFuture::new(fd, &pong).await;
-                            }
-                            _ => {
-                                // ignore other opcodes
-                            }
-                        }
-            }
-
-            if !parsed_any {
-                // need more data; continue recv
+            while let Some(msg) = codec.decode(&mut buf_acc) {
+                match msg {
+                    Message::Text(data) | Message::Binary(data) => {
+                        send_message(fd, &codec, &Message::Binary(data)).await;
+                        // Send ping to keep connection alive
+                        send_message(fd, &codec, &Message::Ping(Vec::new())).await;
+                    }
+                    Message::Ping(payload) => {
+                        send_message(fd, &codec, &Message::Pong(payload)).await;
+                    }
+                    Message::Pong(_) => {
+                        last_pong_ns = sys::clock_gettime_monotonic_ns();
+                        async_runtime::log_write(b"[WS] pong at ns=");
+                        sys::write_usize(
+                            async_runtime::LOG_FD.load(core::sync::atomic::Ordering::Relaxed),
+                            last_pong_ns as usize,
+                        );
+                        async_runtime::log_write(b"\n");
+                    }
+                    Message::Close(parsed) => {
+                        // Echo the peer's status code back (mapped onto a
+                        // code we recognize), or close normally if they
+                        // sent none.
+                        let code = match parsed {
+                            Some((raw, _)) => CloseCode::from_peer(raw),
+                            None => CloseCode::Normal,
+                        };
+                        let reason = match &parsed {
+                            Some((_, reason)) => reason.clone(),
+                            None => Vec::new(),
+                        };
+                        send_message(
+                            fd,
+                            &codec,
+                            &Message::Close(Some((code as u16, reason))),
+                        )
+                        .await;
+                        async_runtime::unregister_fd(fd);
+                        let _ = sys::close(fd);
+                        return;
+                    }
+                }
             }
         }
     } else {